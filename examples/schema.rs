@@ -2,7 +2,9 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cw_auction::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ResolveListingResponse, GFMintMsg, Cw20HookMsg};
+use cw_auction::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ResolveListingResponse, GFMintMsg, Cw20HookMsg, EditionsResponse, CollectionsResponse, MintVoucher, MintPhasesResponse, MetadataStatusResponse,
+    Cw721HookMsg, RedemptionsResponse, RedeemablePiecesResponse, OracleQueryMsg, PriceResponse, PaymentAssetsResponse, OffersResponse, CollectionOffersResponse,
+    EndedUnsettledResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -12,8 +14,23 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ResolveListingResponse), &out_dir);
     export_schema(&schema_for!(GFMintMsg), &out_dir);
     export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(EditionsResponse), &out_dir);
+    export_schema(&schema_for!(CollectionsResponse), &out_dir);
+    export_schema(&schema_for!(MintVoucher), &out_dir);
+    export_schema(&schema_for!(MintPhasesResponse), &out_dir);
+    export_schema(&schema_for!(MetadataStatusResponse), &out_dir);
+    export_schema(&schema_for!(Cw721HookMsg), &out_dir);
+    export_schema(&schema_for!(RedemptionsResponse), &out_dir);
+    export_schema(&schema_for!(RedeemablePiecesResponse), &out_dir);
+    export_schema(&schema_for!(OracleQueryMsg), &out_dir);
+    export_schema(&schema_for!(PriceResponse), &out_dir);
+    export_schema(&schema_for!(PaymentAssetsResponse), &out_dir);
+    export_schema(&schema_for!(OffersResponse), &out_dir);
+    export_schema(&schema_for!(CollectionOffersResponse), &out_dir);
+    export_schema(&schema_for!(EndedUnsettledResponse), &out_dir);
 }
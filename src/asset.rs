@@ -88,7 +88,11 @@ impl Asset{
         }
       }
     } else {
-      Ok(())
+      // cw20 tokens are only escrowed when the token contract calls us through its Receive hook
+      match &self.info {
+        AssetInfo::Token { contract_addr } if *contract_addr == message_info.sender => Ok(()),
+        _ => Err(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")),
+      }
     }
   }
 
@@ -1,21 +1,63 @@
 use cosmwasm_std::{
-    entry_point, to_binary, from_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdError, StdResult, WasmMsg, Uint128, Decimal, QueryRequest, WasmQuery, Addr
+    entry_point, to_binary, to_vec, from_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    Response, StdError, StdResult, WasmMsg, Uint128, Decimal, QueryRequest, WasmQuery, Addr, Storage, Api, Order
 };
-use cw20::Cw20ReceiveMsg;
+use cw20::{Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse};
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ResolveListingResponse, GFMintMsg, Cw20HookMsg};
-use crate::state::{store_config, read_config, store_minters, remove_minter, read_minters, read_minter_info, list_resolver, list_resolver_read, Config, Listing, MinterInfo, Metadata, store_nft_address, read_nft_address};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ResolveListingResponse, GFMintMsg, Cw20HookMsg, EditionsResponse, CollectionsResponse,
+    MintVoucher, MintPhasesResponse, MetadataStatusResponse,
+    Cw721HookMsg, RedemptionsResponse, RedeemablePiecesResponse, DisputeResolution,
+    Cw2981QueryMsg, Cw2981ExtensionQueryMsg, RoyaltiesInfoResponse, CheckRoyaltiesResponse, OracleQueryMsg, PriceResponse, PaymentAssetsResponse, OffersResponse,
+    CollectionOffersResponse, BundleItemMsg, EndedUnsettledResponse, EndedListing};
+use crate::state::{store_config, read_config, store_minters, remove_minter, read_minters, read_minter_info, list_resolver, list_resolver_read, legacy_list_resolver_read, store_listing_end, remove_listing_end, read_ended_listings, Config, Listing, MinterInfo, Metadata, Royalty, NftAsset, store_nft_address, read_nft_address, store_nft_asset, read_nft_asset,
+    Collection, store_collection, read_collection, read_collections,
+    store_voucher_nonce, is_voucher_nonce_used, MintPhase, store_mint_phase, read_mint_phase,
+    remove_mint_phase, read_mint_phases, store_phase_mints, read_phase_mints, asset_id_of,
+    Redemption, RedemptionStatus, store_redemption, read_redemption, read_redemption_ids,
+    EscrowedSale, EscrowStatus, store_escrowed_sale, read_escrowed_sale, remove_escrowed_sale,
+    store_registry_royalties, read_registry_royalties, store_allowed_collection, is_collection_allowed, PriceSource, store_price_source, read_price_source,
+    Offer, store_offer, read_offer, remove_offer, read_offer_ids, read_token_offer_ids, OFFER_REFUNDS,
+    Bid, RetractionRules, AccessRule, ListingItem, EditionAuction, EditionBid, EditionPricing, store_edition_auction,
+    read_edition_auction, remove_edition_auction, KeeperReward, Referrer, store_referrer, read_referrer, PayoutShare, CollectionOffer, OfferTraits, store_collection_offer, read_collection_offer, remove_collection_offer, read_collection_offer_ids,
+    PaymentAsset, store_payment_asset, remove_payment_asset, read_payment_asset, read_payment_assets};
 use cw721::{
     Cw721ExecuteMsg::{Approve, TransferNft},
+    Cw721ReceiveMsg,
     Expiration,
+    NftInfoResponse,
+    OwnerOfResponse,
     TokensResponse
 };
 use crate::asset::{ Asset, AssetInfo };
+use crate::merkle::{ decode_hash, verify_proof };
+use crate::payout::{ PayoutSplit, payout_msgs };
 
 use cw721_base::msg::{ ExecuteMsg as Cw721ExecuteMsg, MintMsg, QueryMsg as Cw721QueryMsg };
 pub const DEFAULT_EXPIRE_BLOCKS: u64 = 50_000;  // in seconds
+pub const DEFAULT_MAX_BATCH_SIZE: u64 = 50;
+// a shipped redemption is considered received after 30 days
+pub const DEFAULT_REDEMPTION_TIMEOUT: u64 = 30 * 24 * 60 * 60;
+// buyers of escrowed sales have 14 days to dispute the delivery
+pub const DEFAULT_DISPUTE_WINDOW: u64 = 14 * 24 * 60 * 60;
+// total royalties of a token are capped at 50% unless the owner changes it
+pub const DEFAULT_MAX_ROYALTY_PERCENT: u64 = 50;
+// bounds of the metadata accepted on mint
+pub const MAX_NAME_LENGTH: usize = 128;
+pub const MAX_DESCRIPTION_LENGTH: usize = 2048;
+pub const MAX_URI_LENGTH: usize = 512;
+pub const MAX_ROYALTY_RECIPIENTS: usize = 10;
+pub const MAX_PAYOUT_RECIPIENTS: usize = 10;
+// display symbols of payment assets, like cw20 token symbols
+pub const MIN_SYMBOL_LENGTH: usize = 3;
+pub const MAX_SYMBOL_LENGTH: usize = 12;
+// outbid bids kept escrowed as fallback of a listing, older ones are refunded
+pub const MAX_PRIOR_BIDS: usize = 10;
+pub const ALLOWED_URI_SCHEMES: [&str; 3] = ["https://", "ipfs://", "ar://"];
+// pagination of list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -28,6 +70,21 @@ pub fn instantiate(
         listing_count: 0,
         owner: info.sender.to_string(),
         max_aution_duration_blocks: DEFAULT_EXPIRE_BLOCKS,
+        max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+        collection_count: 0,
+        phase_count: 0,
+        protocol_fee_rate: Decimal::zero(),
+        treasury: info.sender.to_string(),
+        redemption_count: 0,
+        redemption_timeout: DEFAULT_REDEMPTION_TIMEOUT,
+        arbiter: None,
+        dispute_window: DEFAULT_DISPUTE_WINDOW,
+        max_royalty_rate: Decimal::percent(DEFAULT_MAX_ROYALTY_PERCENT),
+        offer_count: 0,
+        edition_auction_count: 0,
+        keeper_reward: None,
+        referral_rate: Decimal::zero(),
+        min_bid_increment: Decimal::zero(),
     };
     // Initiate listing_id with 0
     store_config(deps.storage, &config_state)?;
@@ -35,6 +92,42 @@ pub fn instantiate(
     Ok(Response::default())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    // fields added to the config since load with their defaults, the treasury is the owner's
+    let mut config = read_config(deps.storage)?;
+    if config.treasury.is_empty() {
+        config.treasury = config.owner.clone();
+    }
+    store_config(deps.storage, &config)?;
+
+    // listings stored before bundles become listings of their single token
+    let listings = list_resolver_read(deps.storage)
+        .range(None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(Vec<u8>, Listing)>>>()?;
+    let mut migrated = 0u64;
+    for (key, mut listing) in listings {
+        if !listing.items.is_empty() {
+            continue;
+        }
+
+        let legacy = legacy_list_resolver_read(deps.storage).load(&key)?;
+        listing.items = vec![ListingItem::new(legacy.contract_addr, legacy.token_id)];
+        listing.accepted_assets = vec![listing.max_bid.clone()];
+        list_resolver(deps.storage).save(&key, &listing)?;
+        store_listing_end(deps.storage, &String::from_utf8_lossy(&key), listing.block_limit)?;
+        migrated += 1;
+    }
+
+    Ok(Response::new()
+        .add_attribute("migrate", "cw-auction")
+        .add_attribute("migrated_listings", migrated.to_string()))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -47,16 +140,101 @@ pub fn execute(
         ExecuteMsg::PlaceListing {
             id,
             minimum_bid,
-        } => execute_place_listing(deps, env, info.clone(), id, minimum_bid, info.sender),
-        ExecuteMsg::BidListing { listing_id, bid_price} => execute_bid_listing(deps, env, info.clone(), listing_id, bid_price, info.sender.clone()),
+            escrow,
+            nft_contract,
+            accepted_assets,
+            retraction,
+            payouts,
+            access,
+        } => execute_place_listing(deps, env, info.clone(), id, minimum_bid, escrow.unwrap_or(false), nft_contract,
+            accepted_assets.unwrap_or_default(), retraction, payouts.unwrap_or_default(), access, info.sender),
+        ExecuteMsg::PlaceBundle { items, minimum_bid, escrow, accepted_assets, retraction, payouts, access }
+            => execute_place_bundle(deps, env, items, minimum_bid, escrow.unwrap_or(false),
+                accepted_assets.unwrap_or_default(), retraction, payouts.unwrap_or_default(), access, info.sender),
+        ExecuteMsg::BidListing { listing_id, bid_price, expires, referrer, proof }
+            => execute_bid_listing(deps, env, info.clone(), listing_id, bid_price, expires, referrer, proof, info.sender.clone()),
+        ExecuteMsg::ProxyBid { listing_id, max_bid, referrer, proof }
+            => execute_proxy_bid(deps, env, info.clone(), listing_id, max_bid, referrer, proof, info.sender.clone()),
+        ExecuteMsg::RetractBid { listing_id } => execute_retract_bid(deps, env, info, listing_id),
+        ExecuteMsg::MakeOffer { collection, token_id, price, expires, referrer }
+            => execute_make_offer(deps, env, info.clone(), collection, token_id, price, expires, referrer, info.sender.clone()),
+        ExecuteMsg::CancelOffer { offer_id } => execute_cancel_offer(deps, env, info, offer_id),
+        ExecuteMsg::RefundOffers { collection, token_id } => execute_refund_offers(deps, collection, token_id),
+        ExecuteMsg::MakeCollectionOffer { collection, price, quantity, traits, expires, referrer } => {
+            // the price is per token, the whole quantity is escrowed
+            let escrow = Asset { info: price.info, amount: price.amount.checked_mul(Uint128::from(quantity))? };
+            escrow.assert_sent_native_token_balance(&info)?;
+            execute_make_collection_offer(deps, env, collection, escrow, quantity, traits, expires, referrer, info.sender)
+        },
+        ExecuteMsg::CancelCollectionOffer { offer_id } => execute_cancel_collection_offer(deps, env, info, offer_id),
         ExecuteMsg::WithdrawListing { listing_id } => {
             execute_withdraw_listing(deps, env, info, listing_id)
         },
+        ExecuteMsg::BatchSettle { listing_ids } => execute_batch_settle(deps, env, info, listing_ids),
         ExecuteMsg::Mint(mint_msg) => execute_mint(deps, env, info, mint_msg),
+        ExecuteMsg::BatchMint{ mints } => execute_batch_mint(deps, env, info, mints),
+        ExecuteMsg::MintEditions{ asset_id, amount, owner } => execute_mint_editions(deps, env, info, asset_id, amount, owner),
         ExecuteMsg::UpdateMinter{ minter } => update_minters(deps, env, info, &minter),
         ExecuteMsg::RemoveMinter{ minter } => unregister_minter(deps, env, info, &minter),
         ExecuteMsg::ReceiveToken(msg) => receive_token(deps, env, info, msg),
+        ExecuteMsg::ReceiveNft(msg) => receive_nft(deps, env, info, msg),
+        ExecuteMsg::ConfirmShipment{ redemption_id, tracking }
+            => execute_confirm_shipment(deps, env, info, redemption_id, tracking),
+        ExecuteMsg::ConfirmReceipt{ redemption_id } => execute_confirm_receipt(deps, env, info, redemption_id),
+        ExecuteMsg::CancelRedemption{ redemption_id } => execute_cancel_redemption(deps, env, info, redemption_id),
         ExecuteMsg::SetNftAddress{nft_address} => set_nft_address(deps, env, info, nft_address),
+        ExecuteMsg::SetMaxBatchSize{ size } => set_max_batch_size(deps, env, info, size),
+        ExecuteMsg::SetProtocolFeeRate{ rate } => set_protocol_fee_rate(deps, env, info, rate),
+        ExecuteMsg::SetTreasury{ treasury } => set_treasury(deps, env, info, treasury),
+        ExecuteMsg::SetRedemptionTimeout{ timeout } => set_redemption_timeout(deps, env, info, timeout),
+        ExecuteMsg::SetArbiter{ arbiter } => set_arbiter(deps, env, info, arbiter),
+        ExecuteMsg::SetDisputeWindow{ window } => set_dispute_window(deps, env, info, window),
+        ExecuteMsg::SetMaxRoyaltyRate{ rate } => set_max_royalty_rate(deps, env, info, rate),
+        ExecuteMsg::ReleaseEscrow{ listing_id } => execute_release_escrow(deps, env, info, listing_id),
+        ExecuteMsg::OpenDispute{ listing_id, reason } => execute_open_dispute(deps, env, info, listing_id, reason),
+        ExecuteMsg::ResolveDispute{ listing_id, resolution }
+            => execute_resolve_dispute(deps, env, info, listing_id, resolution),
+        ExecuteMsg::SetMinterPubkey{ pubkey } => set_minter_pubkey(deps, env, info, pubkey),
+        ExecuteMsg::RedeemVoucher{ voucher, signature } => {
+            let payment = voucher.price.clone();
+            execute_redeem_voucher(deps, env, info.clone(), voucher, signature, payment, info.sender)
+        },
+        ExecuteMsg::RevokeVoucher{ nonce } => execute_revoke_voucher(deps, env, info, nonce),
+        ExecuteMsg::AddMintPhase{ asset_id, start_time, end_time, price, per_address_limit, merkle_root, treasury_share }
+            => execute_add_mint_phase(deps, env, info, asset_id, start_time, end_time, price, per_address_limit, merkle_root, treasury_share),
+        ExecuteMsg::RemoveMintPhase{ phase_id } => execute_remove_mint_phase(deps, env, info, phase_id),
+        ExecuteMsg::UpdateMetadata{ asset_id, name, description, external_link, image_uri, royalties }
+            => execute_update_metadata(deps, env, info, asset_id, name, description, external_link, image_uri, royalties),
+        ExecuteMsg::FreezeMetadata{ asset_id } => execute_freeze_metadata(deps, env, info, asset_id),
+        ExecuteMsg::MintFromPhase{ phase_id, proof } => {
+            let payment = read_mint_phase(deps.storage, phase_id)?.price;
+            execute_mint_from_phase(deps, env, info.clone(), phase_id, proof, payment, info.sender)
+        },
+        ExecuteMsg::PlaceEditionAuction{ asset_id, units, minimum_bid, pricing, max_per_bidder, treasury_share }
+            => execute_place_edition_auction(deps, env, info, asset_id, units, minimum_bid, pricing, max_per_bidder,
+                treasury_share),
+        ExecuteMsg::BidEditions{ auction_id, price, quantity } => {
+            let payment = Asset { info: price.info, amount: price.amount.checked_mul(Uint128::from(quantity))? };
+            execute_bid_editions(deps, env, info.clone(), auction_id, payment, quantity, info.sender)
+        },
+        ExecuteMsg::SettleEditionAuction{ auction_id } => execute_settle_edition_auction(deps, env, auction_id),
+        ExecuteMsg::CreateCollection{ name, description, default_royalties, max_supply, mint_start, mint_end }
+            => execute_create_collection(deps, env, info, name, description, default_royalties, max_supply, mint_start, mint_end),
+        ExecuteMsg::SetCollectionMinting{ collection_id, open }
+            => execute_set_collection_minting(deps, env, info, collection_id, open),
+        ExecuteMsg::SetRoyaltyPayee{ collection_id, payee }
+            => execute_set_royalty_payee(deps, env, info, collection_id, payee),
+        ExecuteMsg::AddPaymentAsset{ info: asset_info, decimals, symbol }
+            => add_payment_asset(deps, env, info, asset_info, decimals, symbol),
+        ExecuteMsg::RemovePaymentAsset{ info: asset_info } => remove_payment_asset_info(deps, env, info, asset_info),
+        ExecuteMsg::SetPriceSource{ source } => set_price_source(deps, env, info, source),
+        ExecuteMsg::SetKeeperReward{ reward } => set_keeper_reward(deps, env, info, reward),
+        ExecuteMsg::SetReferralRate{ rate } => set_referral_rate(deps, env, info, rate),
+        ExecuteMsg::SetMinBidIncrement{ rate } => set_min_bid_increment(deps, env, info, rate),
+        ExecuteMsg::UpdateReferrers{ add, remove } => update_referrers(deps, env, info, add, remove),
+        ExecuteMsg::UpdateAllowedCollections{ add, remove } => update_allowed_collections(deps, env, info, add, remove),
+        ExecuteMsg::SetRegistryRoyalties{ nft_contract, royalties }
+            => set_registry_royalties(deps, env, info, nft_contract, royalties),
     }
 }
 
@@ -77,11 +255,13 @@ fn set_nft_address(
     Ok(Response::default())
 }
 
-fn update_minters(
+fn add_payment_asset(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    minter: &String
+    asset_info: AssetInfo,
+    decimals: u8,
+    symbol: String,
 ) -> Result<Response, ContractError> {
     let config = read_config(deps.storage)?;
     let owner = deps.api.addr_validate(&config.owner)?;
@@ -90,322 +270,3315 @@ fn update_minters(
         return Err(ContractError::Unauthorized{});
     }
 
-    let minter_info = MinterInfo {};
+    // make sure the address is a cw20 token and its amounts are displayed right
+    if let AssetInfo::Token { contract_addr } = &asset_info {
+        let token_info: TokenInfoResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deps.api.addr_validate(contract_addr)?.to_string(),
+            msg: to_binary(&Cw20QueryMsg::TokenInfo {})?,
+        }))?;
 
-    store_minters(deps.storage, deps.api.addr_validate(minter)?, minter_info)?;
-    Ok(Response::default())
+        if token_info.decimals != decimals {
+            return Err(ContractError::DecimalsMismatch { decimals: token_info.decimals });
+        }
+    }
+
+    validate_symbol(&symbol)?;
+
+    if read_payment_asset(deps.storage, &asset_info)?.is_some() {
+        return Err(ContractError::DuplicateAsset { asset: asset_info.to_string() });
+    }
+
+    store_payment_asset(deps.storage, &PaymentAsset { info: asset_info.clone(), decimals, symbol })?;
+    Ok(Response::new().add_attribute("add_payment_asset", asset_info.to_string()))
 }
 
-fn unregister_minter(
+fn remove_payment_asset_info(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    minter: &String
+    asset_info: AssetInfo,
 ) -> Result<Response, ContractError> {
     let config = read_config(deps.storage)?;
     let owner = deps.api.addr_validate(&config.owner)?;
 
-    if info.sender != owner{
+    if info.sender != owner {
         return Err(ContractError::Unauthorized{});
     }
 
-    remove_minter(deps.storage, deps.api.addr_validate(minter)?)?;
-    Ok(Response::default())
+    remove_payment_asset(deps.storage, &asset_info);
+    Ok(Response::new().add_attribute("remove_payment_asset", asset_info.to_string()))
 }
 
-fn receive_token(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    wrapper: Cw20ReceiveMsg
-) -> Result<Response, ContractError>{
-    let msg = from_binary(&wrapper.msg)?;
-    
-    let asset = Asset {
-        info: AssetInfo::Token {contract_addr: info.sender.to_string()},
-        amount: wrapper.amount
-    };
-
-    let sender = deps.api.addr_validate(&wrapper.sender)?;
-    match msg {
-        Cw20HookMsg::BidListing{ listing_id,} 
-            => execute_bid_listing(deps, env, info, listing_id, asset, sender),
-        Cw20HookMsg::PlaceListing{ id }
-            => execute_place_listing(deps, env, info, id, asset, sender),
+fn assert_payment_asset(storage: &dyn Storage, asset_info: &AssetInfo) -> Result<(), ContractError> {
+    match read_payment_asset(storage, asset_info)? {
+        Some(_) => Ok(()),
+        None => Err(ContractError::UnsupportedPaymentAsset { asset: asset_info.to_string() }),
     }
 }
 
-fn execute_mint(
+fn set_price_source(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    msg: GFMintMsg,
+    source: Option<PriceSource>,
 ) -> Result<Response, ContractError> {
-    // check if the sender is a whitelisted minter
-    let minter_info = read_minter_info(deps.storage, info.sender);
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    if minter_info == None {
+    if info.sender != owner {
         return Err(ContractError::Unauthorized{});
     }
 
-    let nft_contract_address = read_nft_address(deps.storage)?;
+    if let Some(PriceSource::Oracle { contract }) = &source {
+        deps.api.addr_validate(contract.as_str())?;
+    }
 
-    // check if royalties are set properly. sum of them must not be greater than 100%
-    let mut sum_total_rate = Decimal::zero();
+    store_price_source(deps.storage, &source)?;
+    Ok(Response::new().add_attribute("price_source", if source.is_some() { "set" } else { "removed" }))
+}
 
-    for royalty in msg.royalties.iter() {
-        sum_total_rate = sum_total_rate + (*royalty).royalty_rate;
-    }
+fn set_keeper_reward(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    reward: Option<KeeperReward>,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    if sum_total_rate > Decimal::one() {
-        return Err(ContractError::InvalidRoyaltyRate {})
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
     }
 
-    let mut config = read_config(deps.storage)?;
-    config.listing_count = config.listing_count + 1;
+    match &reward {
+        Some(KeeperReward::FeeShare { rate }) if *rate > Decimal::one() => {
+            return Err(ContractError::InvalidFeeRate {});
+        },
+        Some(KeeperReward::Fixed { amounts }) => {
+            for (index, amount) in amounts.iter().enumerate() {
+                if amounts[..index].iter().any(|other| other.info == amount.info) {
+                    return Err(ContractError::DuplicateAsset { asset: amount.info.to_string() });
+                }
+            }
+        },
+        _ => {},
+    }
 
+    config.keeper_reward = reward;
     store_config(deps.storage, &config)?;
 
-    let token_id: String = ["GF".to_string(), config.listing_count.to_string()].join(".");
-
-    Ok(Response::new()
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: nft_contract_address.to_string(),
-            msg: to_binary(&Cw721ExecuteMsg::Mint(MintMsg {
-                token_id,
-                owner: msg.owner,
-                token_uri: msg.image_uri,
-                extension: Metadata {
-                    name: msg.name,
-                    description: msg.description,
-                    external_link: msg.external_link,
-                    collection: Some(Uint128::from(1 as u128)),
-                    num_real_repr: msg.num_real_repr,
-                    num_nfts:msg.num_nfts,
-                    royalties: msg.royalties,
-                    init_price: msg.init_price
-                }
-            }))?,
-            funds: vec![]
-        }))
-    )
+    Ok(Response::new().add_attribute("keeper_reward", if config.keeper_reward.is_some() { "set" } else { "removed" }))
 }
 
-pub fn execute_bid_listing(
+fn set_referral_rate(
     deps: DepsMut,
-    env: Env,
+    _env: Env,
     info: MessageInfo,
-    listing_id: String,
-    bid_price: Asset,
-    sender: Addr,
+    rate: Decimal,
 ) -> Result<Response, ContractError> {
-    // check if the bid_price is correct in case of native tokens
-    bid_price.assert_sent_native_token_balance(&info)?;
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    // Fetch listing from listing_id
-    let key = listing_id.as_bytes();
-    let mut listing = list_resolver_read(deps.storage).load(key)?;
-    
-    if listing.block_limit < env.block.height {
-        return Err(ContractError::AuctionEnded {});
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
     }
 
-    // check if the token type is identical to the one in the listing
-    if bid_price.info != listing.max_bid.info {
-        return Err(ContractError::AssetInfoMismatch{});
+    if rate > Decimal::one() {
+        return Err(ContractError::InvalidFeeRate {});
     }
 
-    // check if current bid exceeds the previous one
- 
-    if bid_price.amount < listing.max_bid.amount {
-        return Err(ContractError::InsufficientFundsSend{});
-    } 
+    config.referral_rate = rate;
+    store_config(deps.storage, &config)?;
 
-    // refund former bid
-    let last_bid = listing.max_bid;
-    let last_bidder = listing.max_bidder;
-    let msg = last_bid.into_msg(last_bidder.clone())?;
+    Ok(Response::new().add_attribute("referral_rate", rate.to_string()))
+}
 
-    // update bidder
-    listing.max_bidder = sender.clone();
-    listing.max_bid = bid_price.clone();
-    list_resolver(deps.storage).save(key, &listing)?;
+fn set_min_bid_increment(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    rate: Decimal,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    if env.contract.address != last_bidder {
-    // return money to last bidder
-        Ok(Response::new()
-            .add_attribute("Bidding", listing_id)
-            .add_message(msg))
-    } else {
-        Ok(Response::new().add_attribute("Bidding", listing_id))
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
     }
 
+    if rate > Decimal::one() {
+        return Err(ContractError::InvalidFeeRate {});
+    }
+
+    config.min_bid_increment = rate;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("min_bid_increment", rate.to_string()))
 }
 
-pub fn execute_place_listing(
+fn update_referrers(
     deps: DepsMut,
-    env: Env,
-    _info: MessageInfo,
-    id: String,
-    minimum_bid: Asset,
-    sender: Addr,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
 ) -> Result<Response, ContractError> {
-    let nft_contract_address = read_nft_address(deps.storage)?;
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    // update listing id in store
-    let config_state = read_config(deps.storage)?;
-    let listing_count = config_state.listing_count + 1;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
 
-    // Each auction has a limit for 50000 blocks
-    let listing = Listing {
-        token_id: id.clone(),
-        contract_addr: nft_contract_address.clone(),
-        seller: sender,
-        max_bid: minimum_bid,
-        max_bidder: env.contract.address.clone(),
-        block_limit: env.block.height + config_state.max_aution_duration_blocks,
+    // removed referrers keep their earnings record
+    for (addresses, allowed) in [(add, true), (remove, false)] {
+        for address in addresses {
+            let mut referrer = read_referrer(deps.storage, &deps.api.addr_validate(&address)?)?;
+            referrer.allowed = allowed;
+            store_referrer(deps.storage, &referrer)?;
+        }
+    }
+
+    Ok(Response::new().add_attribute("update_referrers", "done"))
+}
+
+// Referrers must be on the allowlist and cannot refer their own bids
+fn validate_referrer(deps: Deps, referrer: Option<String>, bidder: &Addr) -> Result<Option<Addr>, ContractError> {
+    let referrer = match referrer {
+        Some(referrer) => deps.api.addr_validate(&referrer)?,
+        None => return Ok(None),
     };
 
-    let key = listing_count.to_string();
-    // save listing to store
-    list_resolver(deps.storage).save(key.as_bytes(), &listing)?;
+    if referrer == *bidder || !read_referrer(deps.storage, &referrer)?.allowed {
+        return Err(ContractError::ReferrerNotAllowed { referrer: referrer.to_string() });
+    }
 
-    // lock nft to contract
-    Ok(Response::new()
-        .add_attribute("place_listing", id.to_string())
-        .add_messages(vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: nft_contract_address.to_string(),
-                funds: vec![],
-                msg: to_binary(&Approve {
-                    spender: env.contract.address.to_string(),
-                    token_id: id.clone(),
-                    expires: Some(Expiration::AtHeight(env.block.height + config_state.max_aution_duration_blocks)),
-                })?,
-            }),
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: nft_contract_address.to_string(),
-                funds: vec![],
-                msg: to_binary(&TransferNft {
-                    recipient: String::from(env.contract.address.as_str()),
-                    token_id: id,
-                })?,
-            }),
-        ]))
+    Ok(Some(referrer))
 }
 
-pub fn execute_withdraw_listing(
+fn update_allowed_collections(
     deps: DepsMut,
-    env: Env,
-    _info: MessageInfo,
-    listing_id: String,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
 ) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    let key = listing_id.as_bytes();
-    let listing = list_resolver_read(deps.storage).load(key)?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
 
-    // Check if the auction ended or not
-    if listing.block_limit >= env.block.height {
-        return Err(ContractError::AuctionNotEnded {});
+    // open listings of removed collections can still be settled
+    for (collections, allowed) in [(add, true), (remove, false)] {
+        for collection in collections {
+            store_allowed_collection(deps.storage, &deps.api.addr_validate(&collection)?, allowed)?;
+        }
     }
 
-    let mut msgs = vec![];
-    // remove listing from the store
-    list_resolver(deps.storage).remove(key);
+    Ok(Response::new().add_attribute("update_allowed_collections", "done"))
+}
 
-    // If noone has put a bid then then seller will be sent back with his NFT
-    // Transfer the locked NFT to highest bidder and bid amount to the seller
-    if env.contract.address != listing.max_bidder {
-        // transfer NFT to buyer
-        msgs.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: listing.contract_addr.to_string(),
-            funds: vec![],
-            msg: to_binary(&TransferNft {
-                recipient: listing.max_bidder.to_string(),
-                token_id: listing_id.clone(),
-            })?,
-        }));
+fn set_registry_royalties(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    nft_contract: String,
+    royalties: Vec<Royalty>,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-        // distribute royalties
-        let mut remain_amount = listing.max_bid.amount;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
 
-        let token_info: Metadata = query_nft_info(deps.as_ref(), env, listing.token_id)?;
+    validate_royalties(deps.as_ref(), &royalties)?;
 
-        for royalty in token_info.royalties.iter() {
-            msgs.push((Asset {
-                info: listing.max_bid.info.clone(),
-                amount: listing.max_bid.amount * royalty.royalty_rate
-            }).into_msg(deps.api.addr_validate(&royalty.address)?)?);
+    let nft_contract = deps.api.addr_validate(&nft_contract)?;
+    store_registry_royalties(deps.storage, &nft_contract, &royalties)?;
 
-            remain_amount = remain_amount.checked_sub(listing.max_bid.amount * royalty.royalty_rate)?;
-        }
+    Ok(Response::new().add_attribute("registry_royalties", nft_contract))
+}
 
-        // transfer remain amount to seller
-        msgs.push((Asset {
-            info: listing.max_bid.info,
-            amount: remain_amount
-        }).into_msg(listing.seller.clone())?);
+fn set_max_batch_size(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    size: u64,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-        Ok(Response::new()
-            .add_attribute("listing_sold", listing_id.to_string())
-            .add_messages(msgs))
-    } else {
-        Ok(Response::new()
-            .add_attribute("listing_unsold", listing_id.to_string())
-            .add_messages(vec![CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: listing.contract_addr.to_string(),
-                funds: vec![],
-                msg: to_binary(&TransferNft {
-                    recipient: listing.seller.to_string(),
-                    token_id: listing_id.clone(),
-                })?,
-            }), 
-            ]))
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_binary(&read_config(deps.storage)?),
-        QueryMsg::ResolveListing { id } => query_list_resolver(deps, env, id),
-        QueryMsg::QueryMinter {} => to_binary(&query_minters(deps, env)?),
-        QueryMsg::QueryNftInfo {token_id} => to_binary(&query_nft_info(deps, env, token_id)?),
-        QueryMsg::AllTokens{} => to_binary(&query_all_nft_ids(deps, env)?)
+    if size == 0 {
+        return Err(ContractError::InvalidBatchSize {});
     }
+
+    config.max_batch_size = size;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("max_batch_size", size.to_string()))
 }
 
-pub fn query_nft_info(
-    deps: Deps, 
-    _env: Env,  
-    token_id: String,
-) -> StdResult<Metadata> {
-    let nft_contract_address = read_nft_address(deps.storage)?;
+fn set_protocol_fee_rate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    rate: Decimal,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
 
-    let nft_info: MintMsg<Metadata> = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: nft_contract_address.to_string(),
-        msg: to_binary(&Cw721QueryMsg::NftInfo {token_id})?,
-    }))?;
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
 
-    let meta_data: Metadata = nft_info.extension;
+    if rate > Decimal::one() {
+        return Err(ContractError::InvalidFeeRate {});
+    }
 
-    Ok(meta_data)
-}
+    config.protocol_fee_rate = rate;
+    store_config(deps.storage, &config)?;
 
-pub fn query_all_nft_ids(
-    deps: Deps, 
-    _env: Env,  
-) -> StdResult<Vec<String>> {
-    let nft_contract_address = read_nft_address(deps.storage)?;
+    Ok(Response::new().add_attribute("protocol_fee_rate", rate.to_string()))
+}
 
-    let token_ids: TokensResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: nft_contract_address.to_string(),
+fn set_treasury(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    treasury: String,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    config.treasury = deps.api.addr_validate(&treasury)?.to_string();
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("treasury", config.treasury))
+}
+
+fn set_redemption_timeout(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    timeout: u64,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    config.redemption_timeout = timeout;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("redemption_timeout", timeout.to_string()))
+}
+
+fn set_arbiter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    arbiter: String,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    config.arbiter = Some(deps.api.addr_validate(&arbiter)?.to_string());
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("arbiter", arbiter))
+}
+
+fn set_dispute_window(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    window: u64,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    config.dispute_window = window;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("dispute_window", window.to_string()))
+}
+
+fn set_max_royalty_rate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    rate: Decimal,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    if rate > Decimal::one() {
+        return Err(ContractError::InvalidRoyaltyRate {});
+    }
+
+    config.max_royalty_rate = rate;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("max_royalty_rate", rate.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_create_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+    description: Option<String>,
+    default_royalties: Vec<Royalty>,
+    max_supply: Option<Uint128>,
+    mint_start: Option<u64>,
+    mint_end: Option<u64>,
+) -> Result<Response, ContractError> {
+    // check if the sender is a whitelisted minter
+    let minter_info = read_minter_info(deps.storage, info.sender.clone());
+
+    if minter_info.is_none() {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    validate_royalties(deps.as_ref(), &default_royalties)?;
+
+    if let (Some(start), Some(end)) = (mint_start, mint_end) {
+        if start >= end {
+            return Err(ContractError::InvalidMintWindow {});
+        }
+    }
+
+    let mut config = read_config(deps.storage)?;
+    config.collection_count += 1;
+    store_config(deps.storage, &config)?;
+
+    let collection = Collection {
+        id: Uint128::from(config.collection_count),
+        name,
+        description,
+        creator: info.sender,
+        default_royalties,
+        max_supply,
+        minting_open: true,
+        mint_start,
+        mint_end,
+        num_tokens: Uint128::zero(),
+        reserved_tokens: Uint128::zero(),
+        royalty_payee: None,
+    };
+    store_collection(deps.storage, &collection)?;
+
+    Ok(Response::new().add_attribute("create_collection", collection.id.to_string()))
+}
+
+fn execute_set_collection_minting(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection_id: Uint128,
+    open: bool,
+) -> Result<Response, ContractError> {
+    let mut collection = read_collection(deps.storage, collection_id)
+        .map_err(|_| ContractError::CollectionNotFound { id: collection_id })?;
+
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != collection.creator && info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    collection.minting_open = open;
+    store_collection(deps.storage, &collection)?;
+
+    Ok(Response::new()
+        .add_attribute("collection", collection_id.to_string())
+        .add_attribute("minting_open", open.to_string()))
+}
+
+fn execute_set_royalty_payee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection_id: Uint128,
+    payee: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut collection = read_collection(deps.storage, collection_id)
+        .map_err(|_| ContractError::CollectionNotFound { id: collection_id })?;
+
+    if info.sender != collection.creator {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    collection.royalty_payee = payee.map(|payee| deps.api.addr_validate(&payee)).transpose()?;
+    store_collection(deps.storage, &collection)?;
+
+    Ok(Response::new()
+        .add_attribute("collection", collection_id.to_string())
+        .add_attribute("royalty_payee", collection.royalty_payee.map_or(String::new(), |payee| payee.to_string())))
+}
+
+fn update_minters(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    minter: &str
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let minter = deps.api.addr_validate(minter)?;
+
+    // keep the voucher signing key of an already registered minter
+    let minter_info = read_minter_info(deps.storage, minter.clone())
+        .unwrap_or(MinterInfo { pubkey: None });
+
+    store_minters(deps.storage, minter, minter_info)?;
+    Ok(Response::default())
+}
+
+fn set_minter_pubkey(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    // only registered minters can sign mint vouchers
+    let mut minter_info = read_minter_info(deps.storage, info.sender.clone())
+        .ok_or(ContractError::UnregisteredMinter {})?;
+
+    minter_info.pubkey = Some(pubkey);
+    store_minters(deps.storage, info.sender, minter_info)?;
+
+    Ok(Response::default())
+}
+
+fn unregister_minter(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    minter: &str
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner{
+        return Err(ContractError::Unauthorized{});
+    }
+
+    remove_minter(deps.storage, deps.api.addr_validate(minter)?)?;
+    Ok(Response::default())
+}
+
+fn receive_token(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg
+) -> Result<Response, ContractError>{
+    let asset = Asset {
+        info: AssetInfo::Token {contract_addr: info.sender.to_string()},
+        amount: wrapper.amount
+    };
+
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+
+    // tokens sent without a known hook are returned to the sender
+    let msg = match from_binary(&wrapper.msg) {
+        Ok(msg) => msg,
+        Err(_) => return Ok(Response::new()
+            .add_attribute("refund", asset.to_string())
+            .add_message(asset.into_msg(sender)?)),
+    };
+    match msg {
+        Cw20HookMsg::BidListing{ listing_id, expires, referrer, proof }
+            => execute_bid_listing(deps, env, info, listing_id, asset, expires, referrer, proof, sender),
+        Cw20HookMsg::ProxyBid{ listing_id, referrer, proof }
+            => execute_proxy_bid(deps, env, info, listing_id, asset, referrer, proof, sender),
+        Cw20HookMsg::MakeOffer{ collection, token_id, expires, referrer }
+            => execute_make_offer(deps, env, info, collection, token_id, asset, expires, referrer, sender),
+        Cw20HookMsg::MakeCollectionOffer{ collection, quantity, traits, expires, referrer }
+            => execute_make_collection_offer(deps, env, collection, asset, quantity, traits, expires, referrer, sender),
+        Cw20HookMsg::RedeemVoucher{ voucher, signature }
+            => execute_redeem_voucher(deps, env, info, voucher, signature, asset, sender),
+        Cw20HookMsg::MintFromPhase{ phase_id, proof }
+            => execute_mint_from_phase(deps, env, info, phase_id, proof, asset, sender),
+        Cw20HookMsg::BidEditions{ auction_id, quantity }
+            => execute_bid_editions(deps, env, info, auction_id, asset, quantity, sender),
+    }
+}
+
+fn receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw721ReceiveMsg
+) -> Result<Response, ContractError> {
+    let msg = from_binary(&wrapper.msg)?;
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    match msg {
+        Cw721HookMsg::RequestRedemption{} => {
+            // only nfts of the registered cw721 contract can be redeemed
+            if info.sender != read_nft_address(deps.storage)? {
+                return Err(ContractError::Unauthorized{});
+            }
+            execute_request_redemption(deps, env, wrapper.token_id, sender)
+        },
+        Cw721HookMsg::AcceptOffer{ offer_id }
+            => execute_accept_offer(deps, env, info.sender, wrapper.token_id, offer_id, sender),
+        Cw721HookMsg::FillCollectionOffer{ offer_id }
+            => execute_fill_collection_offer(deps, env, info.sender, wrapper.token_id, offer_id, sender),
+        Cw721HookMsg::DepositBundleItem{ listing_id }
+            => execute_deposit_bundle_item(deps, env, info.sender, wrapper.token_id, listing_id, sender),
+        Cw721HookMsg::PlaceListing{ minimum_bid, escrow, accepted_assets, retraction, payouts, access } => {
+            // the nft is already held by the contract
+            let items = vec![ListingItem::new(info.sender, wrapper.token_id.clone())];
+            let listing_id = store_listing(deps, &env, items, minimum_bid, accepted_assets.unwrap_or_default(),
+                escrow.unwrap_or(false), retraction, payouts.unwrap_or_default(), access, sender)?;
+
+            Ok(Response::new()
+                .add_attribute("place_listing", wrapper.token_id)
+                .add_attribute("listing_id", listing_id))
+        },
+    }
+}
+
+fn execute_request_redemption(
+    deps: DepsMut,
+    env: Env,
+    token_id: String,
+    holder: Addr,
+) -> Result<Response, ContractError> {
+    let mut asset = read_nft_asset(deps.storage, asset_id_of(&token_id))?;
+
+    // reserve one of the physical pieces until the redemption completes or is cancelled
+    if asset.redeemed_pieces >= asset.metadata.num_real_repr {
+        return Err(ContractError::NoRedeemablePieces {});
+    }
+
+    asset.redeemed_pieces += Uint128::from(1u128);
+    store_nft_asset(deps.storage, &asset)?;
+
+    let mut config = read_config(deps.storage)?;
+    config.redemption_count += 1;
+    store_config(deps.storage, &config)?;
+
+    let redemption = Redemption {
+        id: config.redemption_count,
+        token_id,
+        asset_id: asset.asset_id,
+        holder,
+        issuer: asset.creator,
+        status: RedemptionStatus::Requested,
+        tracking: None,
+        requested_at: env.block.time.seconds(),
+        shipped_at: None,
+    };
+    store_redemption(deps.storage, &redemption)?;
+
+    Ok(Response::new()
+        .add_attribute("request_redemption", redemption.id.to_string())
+        .add_attribute("token_id", redemption.token_id))
+}
+
+fn execute_confirm_shipment(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    redemption_id: u64,
+    tracking: String,
+) -> Result<Response, ContractError> {
+    let mut redemption = read_redemption(deps.storage, redemption_id)?;
+
+    if info.sender != redemption.issuer {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    if redemption.status != RedemptionStatus::Requested {
+        return Err(ContractError::InvalidRedemptionStatus {});
+    }
+
+    redemption.status = RedemptionStatus::Shipped;
+    redemption.tracking = Some(tracking);
+    redemption.shipped_at = Some(env.block.time.seconds());
+    store_redemption(deps.storage, &redemption)?;
+
+    Ok(Response::new().add_attribute("confirm_shipment", redemption_id.to_string()))
+}
+
+fn execute_confirm_receipt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    redemption_id: u64,
+) -> Result<Response, ContractError> {
+    let mut redemption = read_redemption(deps.storage, redemption_id)?;
+
+    let shipped_at = match (&redemption.status, redemption.shipped_at) {
+        (RedemptionStatus::Shipped, Some(shipped_at)) => shipped_at,
+        _ => return Err(ContractError::InvalidRedemptionStatus {}),
+    };
+
+    // anybody can complete the redemption once the holder missed the timeout
+    let config = read_config(deps.storage)?;
+    let timed_out = env.block.time.seconds() >= shipped_at + config.redemption_timeout;
+
+    if info.sender != redemption.holder && !timed_out {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    redemption.status = RedemptionStatus::Completed;
+    store_redemption(deps.storage, &redemption)?;
+
+    // the piece has been delivered, so the escrowed nft is burned
+    let nft_contract_address = read_nft_address(deps.storage)?;
+
+    Ok(Response::new()
+        .add_attribute("confirm_receipt", redemption_id.to_string())
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: nft_contract_address.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::<Metadata>::Burn {
+                token_id: redemption.token_id,
+            })?,
+            funds: vec![],
+        })))
+}
+
+fn execute_cancel_redemption(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    redemption_id: u64,
+) -> Result<Response, ContractError> {
+    let mut redemption = read_redemption(deps.storage, redemption_id)?;
+
+    if info.sender != redemption.holder && info.sender != redemption.issuer {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    // nothing can be cancelled once the piece is on its way
+    if redemption.status != RedemptionStatus::Requested {
+        return Err(ContractError::InvalidRedemptionStatus {});
+    }
+
+    redemption.status = RedemptionStatus::Cancelled;
+    store_redemption(deps.storage, &redemption)?;
+
+    // release the reserved piece
+    let mut asset = read_nft_asset(deps.storage, &redemption.asset_id)?;
+    asset.redeemed_pieces = asset.redeemed_pieces.checked_sub(Uint128::from(1u128))?;
+    store_nft_asset(deps.storage, &asset)?;
+
+    let nft_contract_address = read_nft_address(deps.storage)?;
+
+    Ok(Response::new()
+        .add_attribute("cancel_redemption", redemption_id.to_string())
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: nft_contract_address.to_string(),
+            funds: vec![],
+            msg: to_binary(&TransferNft {
+                recipient: redemption.holder.to_string(),
+                token_id: redemption.token_id,
+            })?,
+        })))
+}
+
+fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: GFMintMsg,
+) -> Result<Response, ContractError> {
+    // check if the sender is a whitelisted minter
+    let minter_info = read_minter_info(deps.storage, info.sender.clone());
+
+    if minter_info.is_none() {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let nft_contract_address = read_nft_address(deps.storage)?;
+
+    // check the target collection and fill in its default royalties
+    let msg = prepare_mint(deps.as_ref(), env.block.time.seconds(), &info.sender, msg)?;
+
+    let mut config = read_config(deps.storage)?;
+    let owner = msg.owner.clone();
+    let amount = initial_editions(&msg, config.max_batch_size);
+    let mut asset = create_asset(&mut config, info.sender, msg);
+
+    let msgs = mint_editions(deps.storage, env.block.time.seconds(), &nft_contract_address, &mut asset, &owner, amount, false)?;
+
+    store_config(deps.storage, &config)?;
+    store_nft_asset(deps.storage, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("mint", asset.asset_id)
+        .add_messages(msgs)
+    )
+}
+
+fn execute_batch_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mints: Vec<GFMintMsg>,
+) -> Result<Response, ContractError> {
+    // check if the sender is a whitelisted minter
+    let minter_info = read_minter_info(deps.storage, info.sender.clone());
+
+    if minter_info.is_none() {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let mut config = read_config(deps.storage)?;
+
+    if mints.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+
+    // every edition minted right away counts towards the batch limit
+    let total_editions: u64 = mints.iter()
+        .map(|msg| initial_editions(msg, config.max_batch_size))
+        .sum();
+
+    if mints.len() as u64 > config.max_batch_size || total_editions > config.max_batch_size {
+        return Err(ContractError::BatchSizeExceeded { max: config.max_batch_size });
+    }
+
+    let nft_contract_address = read_nft_address(deps.storage)?;
+
+    // validate every royalty set before allocating any token id
+    let mints = mints.into_iter()
+        .map(|msg| prepare_mint(deps.as_ref(), env.block.time.seconds(), &info.sender, msg))
+        .collect::<Result<Vec<GFMintMsg>, ContractError>>()?;
+
+    let mut msgs = vec![];
+    for msg in mints.into_iter() {
+        let owner = msg.owner.clone();
+        let amount = initial_editions(&msg, config.max_batch_size);
+        let mut asset = create_asset(&mut config, info.sender.clone(), msg);
+
+        msgs.extend(mint_editions(deps.storage, env.block.time.seconds(), &nft_contract_address, &mut asset, &owner, amount, false)?);
+        store_nft_asset(deps.storage, &asset)?;
+    }
+
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("batch_mint", msgs.len().to_string())
+        .add_messages(msgs)
+    )
+}
+
+fn execute_mint_editions(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset_id: String,
+    amount: u64,
+    owner: String,
+) -> Result<Response, ContractError> {
+    // check if the sender is a whitelisted minter
+    let minter_info = read_minter_info(deps.storage, info.sender.clone());
+
+    if minter_info.is_none() {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let mut asset = read_nft_asset(deps.storage, &asset_id)?;
+
+    // only the creator of the asset can mint more of its editions
+    if info.sender != asset.creator {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let config = read_config(deps.storage)?;
+
+    if amount == 0 {
+        return Err(ContractError::EmptyBatch {});
+    }
+
+    if amount > config.max_batch_size {
+        return Err(ContractError::BatchSizeExceeded { max: config.max_batch_size });
+    }
+
+    let nft_contract_address = read_nft_address(deps.storage)?;
+    let owner = deps.api.addr_validate(&owner)?;
+
+    let msgs = mint_editions(deps.storage, env.block.time.seconds(), &nft_contract_address, &mut asset, owner.as_str(), amount, false)?;
+    store_nft_asset(deps.storage, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("mint_editions", asset_id)
+        .add_messages(msgs)
+    )
+}
+
+// check the collection a mint targets and apply its default royalties when none are given
+fn prepare_mint(
+    deps: Deps,
+    now: u64,
+    sender: &Addr,
+    mut msg: GFMintMsg,
+) -> Result<GFMintMsg, ContractError> {
+    validate_mint_msg(deps.api, &msg)?;
+
+    if let Some(collection_id) = msg.collection {
+        let collection = read_collection(deps.storage, collection_id)
+            .map_err(|_| ContractError::CollectionNotFound { id: collection_id })?;
+
+        if collection.creator != *sender {
+            return Err(ContractError::Unauthorized{});
+        }
+
+        if !collection.is_minting_open(now) {
+            return Err(ContractError::CollectionMintingClosed { id: collection_id });
+        }
+
+        if msg.royalties.is_empty() {
+            msg.royalties = collection.default_royalties;
+        }
+    }
+
+    // check if royalties are set properly. sum of them must not be greater than 100%
+    validate_royalties(deps, &msg.royalties)?;
+
+    Ok(msg)
+}
+
+// number of editions minted together with the asset itself, the rest is left to MintEditions
+fn initial_editions(msg: &GFMintMsg, max_batch_size: u64) -> u64 {
+    if msg.num_nfts.u128() > max_batch_size as u128 {
+        max_batch_size
+    } else {
+        msg.num_nfts.u128() as u64
+    }
+}
+
+// allocate a new asset id and build the parent record shared by all of its editions
+fn create_asset(config: &mut Config, creator: Addr, msg: GFMintMsg) -> NftAsset {
+    config.listing_count += 1;
+
+    NftAsset {
+        asset_id: ["GF".to_string(), config.listing_count.to_string()].join("."),
+        creator,
+        token_uri: msg.image_uri,
+        metadata: Metadata {
+            name: msg.name,
+            description: msg.description,
+            external_link: msg.external_link,
+            collection: msg.collection,
+            num_real_repr: msg.num_real_repr,
+            num_nfts: msg.num_nfts,
+            royalties: msg.royalties,
+            init_price: msg.init_price
+        },
+        minted_editions: 0,
+        frozen: false,
+        sold: false,
+        redeemed_pieces: Uint128::zero(),
+        reserved_editions: 0,
+    }
+}
+
+fn mint_editions(
+    storage: &mut dyn Storage,
+    now: u64,
+    nft_contract_address: &Addr,
+    asset: &mut NftAsset,
+    owner: &str,
+    amount: u64,
+    reserved: bool,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if (asset.minted_editions + asset.reserved_editions + amount) as u128 > asset.metadata.num_nfts.u128() {
+        return Err(ContractError::EditionLimitExceeded { max: asset.metadata.num_nfts });
+    }
+
+    // every edition counts towards the supply of its collection, editions reserved by an
+    // edition auction already hold their place even if the collection closed since
+    if let Some(collection_id) = asset.metadata.collection {
+        let mut collection = read_collection(storage, collection_id)?;
+
+        if reserved {
+            collection.reserved_tokens = collection.reserved_tokens.checked_sub(Uint128::from(amount))?;
+        } else {
+            if !collection.is_minting_open(now) {
+                return Err(ContractError::CollectionMintingClosed { id: collection_id });
+            }
+
+            if let Some(max_supply) = collection.max_supply {
+                if collection.num_tokens + collection.reserved_tokens + Uint128::from(amount) > max_supply {
+                    return Err(ContractError::CollectionSupplyExceeded { max: max_supply });
+                }
+            }
+        }
+
+        collection.num_tokens = collection.num_tokens.checked_add(Uint128::from(amount))?;
+        store_collection(storage, &collection)?;
+    }
+
+    let mut msgs = vec![];
+    for _ in 0..amount {
+        asset.minted_editions += 1;
+        msgs.push(mint_nft_msg(
+            nft_contract_address,
+            asset.edition_token_id(asset.minted_editions),
+            owner.to_string(),
+            asset.token_uri.clone(),
+            asset.metadata.clone(),
+        )?);
+    }
+
+    Ok(msgs)
+}
+
+// Hash signed by the minter, bound to this contract so vouchers can not be replayed elsewhere
+pub fn voucher_hash(contract_addr: &Addr, voucher: &MintVoucher) -> StdResult<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    hasher.update(contract_addr.as_bytes());
+    hasher.update(to_vec(voucher)?);
+
+    Ok(hasher.finalize().to_vec())
+}
+
+pub fn execute_redeem_voucher(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    voucher: MintVoucher,
+    signature: Binary,
+    payment: Asset,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    // check if the payment is correct in case of native tokens
+    payment.assert_sent_native_token_balance(&info)?;
+
+    if payment != voucher.price {
+        return Err(ContractError::TokenMismatch {});
+    }
+
+    // the price pays for one edition, more are minted with MintEditions
+    if voucher.mint.num_nfts != Uint128::from(1u128) {
+        return Err(ContractError::InvalidVoucherEditions {});
+    }
+
+    let minter = deps.api.addr_validate(&voucher.minter)?;
+    let minter_info = read_minter_info(deps.storage, minter.clone())
+        .ok_or(ContractError::UnregisteredMinter {})?;
+    let pubkey = minter_info.pubkey.ok_or(ContractError::MinterKeyNotSet {})?;
+
+    if voucher.expires.is_expired(&env.block) {
+        return Err(ContractError::VoucherExpired {});
+    }
+
+    if is_voucher_nonce_used(deps.storage, &minter, voucher.nonce)? {
+        return Err(ContractError::VoucherNonceUsed {});
+    }
+
+    let hash = voucher_hash(&env.contract.address, &voucher)?;
+    let verified = deps.api.secp256k1_verify(&hash, &signature, &pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+
+    if !verified {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    store_voucher_nonce(deps.storage, &minter, voucher.nonce)?;
+
+    // mint on behalf of the minter straight to the buyer
+    let nft_contract_address = read_nft_address(deps.storage)?;
+    let mut mint_msg = voucher.mint;
+    mint_msg.owner = buyer.to_string();
+    let mint_msg = prepare_mint(deps.as_ref(), env.block.time.seconds(), &minter, mint_msg)?;
+
+    let mut config = read_config(deps.storage)?;
+
+    // royalties and the protocol fee are paid out of the price
+    let royalty_rate = mint_msg.royalties.iter().fold(Decimal::zero(), |total, royalty| total + royalty.royalty_rate);
+    if royalty_rate + config.protocol_fee_rate > Decimal::one() {
+        return Err(ContractError::VoucherFeesExceedPrice {});
+    }
+
+    let amount = initial_editions(&mint_msg, config.max_batch_size);
+    let mut asset = create_asset(&mut config, minter.clone(), mint_msg);
+
+    let mut msgs = mint_editions(deps.storage, env.block.time.seconds(), &nft_contract_address, &mut asset, buyer.as_str(), amount, false)?;
+
+    // the voucher is a sale, so its royalties are final from now on
+    asset.sold = true;
+
+    store_config(deps.storage, &config)?;
+    store_nft_asset(deps.storage, &asset)?;
+
+    msgs.extend(sale_payout_msgs(deps.as_ref(), &config, &payment, &asset, &minter)?);
+
+    Ok(Response::new()
+        .add_attribute("redeem_voucher", asset.asset_id)
+        .add_attribute("nonce", voucher.nonce.to_string())
+        .add_messages(msgs))
+}
+
+fn execute_revoke_voucher(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    nonce: u64,
+) -> Result<Response, ContractError> {
+    if read_minter_info(deps.storage, info.sender.clone()).is_none() {
+        return Err(ContractError::UnregisteredMinter {});
+    }
+
+    // a revoked nonce can never be redeemed
+    store_voucher_nonce(deps.storage, &info.sender, nonce)?;
+
+    Ok(Response::new().add_attribute("revoke_voucher", nonce.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_add_mint_phase(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset_id: String,
+    start_time: u64,
+    end_time: u64,
+    price: Asset,
+    per_address_limit: Option<u32>,
+    merkle_root: Option<String>,
+    treasury_share: Decimal,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    // phases sell the remaining editions of an existing asset
+    read_nft_asset(deps.storage, &asset_id)?;
+
+    if start_time >= end_time {
+        return Err(ContractError::InvalidPhaseTime {});
+    }
+
+    if treasury_share > Decimal::one() {
+        return Err(ContractError::InvalidFeeRate {});
+    }
+
+    if let Some(root) = merkle_root.as_ref() {
+        decode_hash(root).map_err(|_| ContractError::InvalidMerkleRoot {})?;
+    }
+
+    config.phase_count += 1;
+    store_config(deps.storage, &config)?;
+
+    let phase = MintPhase {
+        id: config.phase_count,
+        asset_id,
+        start_time,
+        end_time,
+        price,
+        per_address_limit,
+        merkle_root,
+        treasury_share,
+        minted: 0,
+    };
+    store_mint_phase(deps.storage, &phase)?;
+
+    Ok(Response::new().add_attribute("add_mint_phase", phase.id.to_string()))
+}
+
+fn execute_remove_mint_phase(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    phase_id: u64,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    remove_mint_phase(deps.storage, phase_id);
+
+    Ok(Response::new().add_attribute("remove_mint_phase", phase_id.to_string()))
+}
+
+pub fn execute_mint_from_phase(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    phase_id: u64,
+    proof: Option<Vec<String>>,
+    payment: Asset,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    // check if the payment is correct in case of native tokens
+    payment.assert_sent_native_token_balance(&info)?;
+
+    let mut phase = read_mint_phase(deps.storage, phase_id)?;
+
+    if payment != phase.price {
+        return Err(ContractError::TokenMismatch {});
+    }
+
+    let now = env.block.time.seconds();
+    if now < phase.start_time || now >= phase.end_time {
+        return Err(ContractError::PhaseNotActive {});
+    }
+
+    // allowlisted phases require a proof of the buyer address
+    if let Some(root) = phase.merkle_root.as_ref() {
+        let proof = proof.unwrap_or_default();
+        if !verify_proof(root, buyer.as_bytes(), &proof)? {
+            return Err(ContractError::NotAllowlisted {});
+        }
+    }
+
+    let minted = read_phase_mints(deps.storage, phase_id, &buyer)? + 1;
+    if let Some(limit) = phase.per_address_limit {
+        if minted > limit {
+            return Err(ContractError::PhaseLimitReached { limit });
+        }
+    }
+    store_phase_mints(deps.storage, phase_id, &buyer, minted)?;
+
+    phase.minted += 1;
+    store_mint_phase(deps.storage, &phase)?;
+
+    let nft_contract_address = read_nft_address(deps.storage)?;
+    let mut asset = read_nft_asset(deps.storage, &phase.asset_id)?;
+
+    let mut msgs = mint_editions(deps.storage, env.block.time.seconds(), &nft_contract_address, &mut asset, buyer.as_str(), 1, false)?;
+    asset.sold = true;
+    store_nft_asset(deps.storage, &asset)?;
+
+    // primary sale proceeds are split between the creator and the treasury,
+    // they are no royalties so a collection royalty payee does not receive them
+    let config = read_config(deps.storage)?;
+    let mut split = PayoutSplit::new(payment.amount);
+    split.add_share(deps.api.addr_validate(&config.treasury)?, phase.treasury_share)?;
+    msgs.extend(payout_msgs(&payment.info, split.finish(asset.creator.clone())?)?);
+
+    Ok(Response::new()
+        .add_attribute("mint_from_phase", phase_id.to_string())
+        .add_attribute("token_id", asset.edition_token_id(asset.minted_editions))
+        .add_messages(msgs))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_place_edition_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset_id: String,
+    units: u64,
+    minimum_bid: Asset,
+    pricing: EditionPricing,
+    max_per_bidder: Option<u64>,
+    treasury_share: Decimal,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let owner = deps.api.addr_validate(&config.owner)?;
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    if units == 0 {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if units > config.max_batch_size {
+        return Err(ContractError::BatchSizeExceeded { max: config.max_batch_size });
+    }
+
+    if max_per_bidder == Some(0) {
+        return Err(ContractError::InvalidBidQuantity {});
+    }
+
+    if treasury_share > Decimal::one() {
+        return Err(ContractError::InvalidFeeRate {});
+    }
+
+    assert_payment_asset(deps.storage, &minimum_bid.info)?;
+
+    // the units can not be minted otherwise until the auction is settled
+    let mut asset = read_nft_asset(deps.storage, &asset_id)?;
+    if (asset.minted_editions + asset.reserved_editions + units) as u128 > asset.metadata.num_nfts.u128() {
+        return Err(ContractError::EditionLimitExceeded { max: asset.metadata.num_nfts });
+    }
+    asset.reserved_editions += units;
+    store_nft_asset(deps.storage, &asset)?;
+
+    // the collection keeps room for the units until the auction is settled
+    if let Some(collection_id) = asset.metadata.collection {
+        let mut collection = read_collection(deps.storage, collection_id)?;
+
+        if !collection.is_minting_open(env.block.time.seconds()) {
+            return Err(ContractError::CollectionMintingClosed { id: collection_id });
+        }
+
+        collection.reserved_tokens = collection.reserved_tokens.checked_add(Uint128::from(units))?;
+
+        if let Some(max_supply) = collection.max_supply {
+            if collection.num_tokens + collection.reserved_tokens > max_supply {
+                return Err(ContractError::CollectionSupplyExceeded { max: max_supply });
+            }
+        }
+
+        store_collection(deps.storage, &collection)?;
+    }
+
+    config.edition_auction_count += 1;
+    store_config(deps.storage, &config)?;
+
+    let auction = EditionAuction {
+        id: config.edition_auction_count,
+        asset_id,
+        units,
+        minimum_bid,
+        pricing,
+        max_per_bidder,
+        treasury_share,
+        block_limit: env.block.height + config.max_aution_duration_blocks,
+        bids: vec![],
+    };
+    store_edition_auction(deps.storage, &auction)?;
+
+    Ok(Response::new().add_attribute("place_edition_auction", auction.id.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_bid_editions(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+    payment: Asset,
+    quantity: u64,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    // the whole payment is escrowed
+    payment.assert_sent_native_token_balance(&info)?;
+
+    let mut auction = read_edition_auction(deps.storage, auction_id)?;
+
+    if auction.block_limit < env.block.height {
+        return Err(ContractError::AuctionEnded {});
+    }
+
+    if payment.info != auction.minimum_bid.info {
+        return Err(ContractError::AssetInfoMismatch{});
+    }
+
+    if quantity == 0 || !payment.amount.u128().is_multiple_of(quantity as u128) {
+        return Err(ContractError::InvalidBidQuantity {});
+    }
+    let price = Uint128::from(payment.amount.u128() / quantity as u128);
+
+    if price < auction.minimum_bid.amount {
+        return Err(ContractError::InsufficientFundsSend{});
+    }
+
+    if let Some(limit) = auction.max_per_bidder {
+        let held: u64 = auction.bids.iter().filter(|bid| bid.bidder == sender).map(|bid| bid.quantity).sum();
+        if held + quantity > limit {
+            return Err(ContractError::BidderLimitReached { limit });
+        }
+    }
+
+    // ties go to the earlier bids
+    let position = auction.bids.iter().position(|bid| bid.price < price).unwrap_or(auction.bids.len());
+    auction.bids.insert(position, EditionBid { bidder: sender, price, quantity });
+
+    // units pushed out of the book are refunded right away
+    let mut msgs = vec![];
+    let mut remaining = auction.units;
+    let mut bids = vec![];
+    for (index, mut bid) in auction.bids.into_iter().enumerate() {
+        let filled = bid.quantity.min(remaining);
+        remaining -= filled;
+
+        if filled < bid.quantity {
+            // a bid winning no unit is rejected
+            if index == position && filled == 0 {
+                return Err(ContractError::InsufficientFundsSend{});
+            }
+            let refund = bid.price.checked_mul(Uint128::from(bid.quantity - filled))?;
+            msgs.push(Asset { info: payment.info.clone(), amount: refund }.into_msg(bid.bidder.clone())?);
+        }
+
+        if filled > 0 {
+            bid.quantity = filled;
+            bids.push(bid);
+        }
+    }
+    auction.bids = bids;
+    store_edition_auction(deps.storage, &auction)?;
+
+    Ok(Response::new()
+        .add_attribute("bid_editions", auction_id.to_string())
+        .add_attribute("price", price.to_string())
+        .add_messages(msgs))
+}
+
+pub fn execute_settle_edition_auction(
+    deps: DepsMut,
+    env: Env,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    let auction = read_edition_auction(deps.storage, auction_id)?;
+
+    if auction.block_limit >= env.block.height {
+        return Err(ContractError::AuctionNotEnded {});
+    }
+
+    remove_edition_auction(deps.storage, auction_id);
+
+    let nft_contract_address = read_nft_address(deps.storage)?;
+    let mut asset = read_nft_asset(deps.storage, &auction.asset_id)?;
+    asset.reserved_editions -= auction.units;
+
+    // uniform pricing clears at the lowest winning bid
+    let mut msgs = vec![];
+    let mut proceeds = Uint128::zero();
+    let mut units_sold = 0;
+    for bid in auction.bids.iter() {
+        msgs.extend(mint_editions(deps.storage, env.block.time.seconds(), &nft_contract_address, &mut asset, bid.bidder.as_str(), bid.quantity, true)?);
+        units_sold += bid.quantity;
+
+        let price = match auction.pricing {
+            EditionPricing::Uniform => auction.bids[auction.bids.len() - 1].price,
+            EditionPricing::Discriminatory => bid.price,
+        };
+        let quantity = Uint128::from(bid.quantity);
+        proceeds += price.checked_mul(quantity)?;
+
+        let refund = bid.price.checked_sub(price)?.checked_mul(quantity)?;
+        if !refund.is_zero() {
+            msgs.push(Asset { info: auction.minimum_bid.info.clone(), amount: refund }.into_msg(bid.bidder.clone())?);
+        }
+    }
+    if units_sold > 0 {
+        asset.sold = true;
+    }
+    store_nft_asset(deps.storage, &asset)?;
+
+    // unsold units give their room back to the collection
+    if let Some(collection_id) = asset.metadata.collection {
+        let mut collection = read_collection(deps.storage, collection_id)?;
+        collection.reserved_tokens = collection.reserved_tokens.checked_sub(Uint128::from(auction.units - units_sold))?;
+        store_collection(deps.storage, &collection)?;
+    }
+
+    // primary sale proceeds are split between the creator and the treasury,
+    // they are no royalties so a collection royalty payee does not receive them
+    let config = read_config(deps.storage)?;
+    let mut split = PayoutSplit::new(proceeds);
+    split.add_share(deps.api.addr_validate(&config.treasury)?, auction.treasury_share)?;
+    msgs.extend(payout_msgs(&auction.minimum_bid.info, split.finish(asset.creator.clone())?)?);
+
+    Ok(Response::new()
+        .add_attribute("settle_edition_auction", auction_id.to_string())
+        .add_attribute("units_sold", units_sold.to_string())
+        .add_messages(msgs))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_update_metadata(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset_id: String,
+    name: Option<String>,
+    description: Option<String>,
+    external_link: Option<String>,
+    image_uri: Option<String>,
+    royalties: Option<Vec<Royalty>>,
+) -> Result<Response, ContractError> {
+    let mut asset = read_nft_asset(deps.storage, &asset_id)?;
+
+    if info.sender != asset.creator {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    if asset.frozen {
+        return Err(ContractError::MetadataFrozen {});
+    }
+
+    if let Some(name) = name {
+        validate_name(&name)?;
+        asset.metadata.name = name;
+    }
+    if description.is_some() {
+        validate_description(&description)?;
+        asset.metadata.description = description;
+    }
+    if external_link.is_some() {
+        validate_uri(&external_link)?;
+        asset.metadata.external_link = external_link;
+    }
+    if image_uri.is_some() {
+        validate_uri(&image_uri)?;
+        asset.token_uri = image_uri;
+    }
+    if let Some(royalties) = royalties {
+        // buyers of a sold asset rely on the royalties it was sold with
+        if asset.sold {
+            return Err(ContractError::RoyaltiesLocked {});
+        }
+        validate_royalties(deps.as_ref(), &royalties)?;
+        asset.metadata.royalties = royalties;
+    }
+
+    store_nft_asset(deps.storage, &asset)?;
+
+    Ok(Response::new()
+        .add_attribute("update_metadata", asset_id))
+}
+
+fn execute_freeze_metadata(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset_id: String,
+) -> Result<Response, ContractError> {
+    let mut asset = read_nft_asset(deps.storage, &asset_id)?;
+
+    if info.sender != asset.creator {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    if asset.frozen {
+        return Err(ContractError::MetadataFrozen {});
+    }
+
+    asset.frozen = true;
+    store_nft_asset(deps.storage, &asset)?;
+
+    Ok(Response::new().add_attribute("freeze_metadata", asset_id))
+}
+
+// remember that an asset changed hands for a price, which locks its royalties
+fn mark_asset_sold(storage: &mut dyn Storage, contract_addr: &Addr, token_id: &str) -> StdResult<()> {
+    if let Some(mut asset) = unsold_asset(storage, contract_addr, token_id)? {
+        asset.sold = true;
+        store_nft_asset(storage, &asset)?;
+    }
+
+    Ok(())
+}
+
+// the asset of a token that still has to be marked sold
+fn unsold_asset(storage: &dyn Storage, contract_addr: &Addr, token_id: &str) -> StdResult<Option<NftAsset>> {
+    // assets only exist for the registered collection
+    if read_nft_address(storage)? != *contract_addr {
+        return Ok(None);
+    }
+
+    Ok(read_nft_asset(storage, asset_id_of(token_id)).ok().filter(|asset| !asset.sold))
+}
+
+// split a sale of an asset into royalties, protocol fee and the remainder for the seller
+fn sale_payout_msgs(
+    deps: Deps,
+    config: &Config,
+    price: &Asset,
+    asset: &NftAsset,
+    seller: &Addr,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut split = PayoutSplit::new(price.amount);
+
+    // the royalty cap and the collection payee apply like on secondary sales
+    let royalties = asset.metadata.royalties.iter()
+        .map(|royalty| Ok((deps.api.addr_validate(&royalty.address)?, price.amount * royalty.royalty_rate)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let nft_contract_address = read_nft_address(deps.storage)?;
+    for (recipient, amount) in apply_royalty_rules(deps, &nft_contract_address, &asset.asset_id, price.amount, royalties)? {
+        split.add_amount(recipient, amount)?;
+    }
+    split.add_share(deps.api.addr_validate(&config.treasury)?, config.protocol_fee_rate)?;
+
+    Ok(payout_msgs(&price.info, split.finish(seller.clone())?)?)
+}
+
+fn validate_mint_msg(api: &dyn Api, msg: &GFMintMsg) -> Result<(), ContractError> {
+    api.addr_validate(&msg.owner)?;
+
+    validate_name(&msg.name)?;
+    validate_description(&msg.description)?;
+    validate_uri(&msg.image_uri)?;
+    validate_uri(&msg.external_link)?;
+
+    if msg.num_nfts.is_zero() {
+        return Err(ContractError::ZeroSupply {});
+    }
+
+    // every physical piece must be backed by an nft
+    if msg.num_real_repr > msg.num_nfts {
+        return Err(ContractError::InvalidRealRepr {});
+    }
+
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<(), ContractError> {
+    if name.trim().is_empty() {
+        return Err(ContractError::EmptyName {});
+    }
+
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(ContractError::NameTooLong { max: MAX_NAME_LENGTH });
+    }
+
+    Ok(())
+}
+
+fn validate_symbol(symbol: &str) -> Result<(), ContractError> {
+    let valid_length = (MIN_SYMBOL_LENGTH..=MAX_SYMBOL_LENGTH).contains(&symbol.len());
+    if !valid_length || !symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(ContractError::InvalidSymbol { min: MIN_SYMBOL_LENGTH, max: MAX_SYMBOL_LENGTH });
+    }
+
+    Ok(())
+}
+
+fn validate_description(description: &Option<String>) -> Result<(), ContractError> {
+    match description {
+        Some(description) if description.len() > MAX_DESCRIPTION_LENGTH
+            => Err(ContractError::DescriptionTooLong { max: MAX_DESCRIPTION_LENGTH }),
+        _ => Ok(()),
+    }
+}
+
+fn validate_uri(uri: &Option<String>) -> Result<(), ContractError> {
+    if let Some(uri) = uri {
+        if uri.len() > MAX_URI_LENGTH {
+            return Err(ContractError::UriTooLong { max: MAX_URI_LENGTH });
+        }
+
+        if !ALLOWED_URI_SCHEMES.iter().any(|scheme| uri.starts_with(scheme)) {
+            return Err(ContractError::InvalidUriScheme { uri: uri.clone() });
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_royalties(deps: Deps, royalties: &[Royalty]) -> Result<(), ContractError> {
+    if royalties.len() > MAX_ROYALTY_RECIPIENTS {
+        return Err(ContractError::TooManyRoyaltyRecipients { max: MAX_ROYALTY_RECIPIENTS });
+    }
+
+    let mut sum_total_rate = Decimal::zero();
+    let mut recipients: Vec<Addr> = vec![];
+
+    for royalty in royalties.iter() {
+        let recipient = deps.api.addr_validate(&royalty.address)
+            .map_err(|_| ContractError::InvalidRoyaltyAddress { address: royalty.address.clone() })?;
+
+        if recipients.contains(&recipient) {
+            return Err(ContractError::DuplicateRoyaltyRecipient { address: royalty.address.clone() });
+        }
+        recipients.push(recipient);
+
+        sum_total_rate = sum_total_rate + royalty.royalty_rate;
+    }
+
+    if sum_total_rate > Decimal::one() {
+        return Err(ContractError::InvalidRoyaltyRate {})
+    }
+
+    let max_royalty_rate = read_config(deps.storage)?.max_royalty_rate;
+    if sum_total_rate > max_royalty_rate {
+        return Err(ContractError::RoyaltyCapExceeded { max: max_royalty_rate });
+    }
+
+    Ok(())
+}
+
+fn mint_nft_msg(
+    nft_contract_address: &Addr,
+    token_id: String,
+    owner: String,
+    token_uri: Option<String>,
+    extension: Metadata,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft_contract_address.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::Mint(MintMsg {
+            token_id,
+            owner,
+            token_uri,
+            extension,
+        }))?,
+        funds: vec![]
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_bid_listing(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+    bid_price: Asset,
+    expires: Option<Expiration>,
+    referrer: Option<String>,
+    proof: Option<Vec<String>>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    // check if the bid_price is correct in case of native tokens
+    bid_price.assert_sent_native_token_balance(&info)?;
+
+    if expires.is_some_and(|expires| expires.is_expired(&env.block)) {
+        return Err(ContractError::BidExpired{});
+    }
+    let referrer = validate_referrer(deps.as_ref(), referrer, &sender)?;
+    let increment = read_config(deps.storage)?.min_bid_increment;
+
+    // Fetch listing from listing_id
+    let key = listing_id.as_bytes();
+    let (mut listing, _) = load_biddable_listing(deps.as_ref(), &env, &listing_id, &bid_price)?;
+    assert_bidder_access(deps.as_ref(), &listing, &sender, proof)?;
+    let mut msgs = drop_expired_bid(&mut listing, &env)?;
+
+    match listing.proxy_max.clone() {
+        // the leading proxy raises on behalf of its bidder up to its maximum
+        Some(proxy_max) => {
+            if bid_price.info != proxy_max.info {
+                return Err(ContractError::AssetInfoMismatch{});
+            }
+            if bid_price.amount < next_bid(listing.max_bid.amount, increment) {
+                return Err(ContractError::InsufficientFundsSend{});
+            }
+
+            if bid_price.amount <= proxy_max.amount {
+                listing.max_bid.amount = proxy_price(bid_price.amount, proxy_max.amount, increment);
+                list_resolver(deps.storage).save(key, &listing)?;
+
+                msgs.push(bid_price.into_msg(sender)?);
+                return Ok(Response::new()
+                    .add_attribute("Bidding", listing_id)
+                    .add_attribute("outbid_by_proxy", listing.max_bid.to_string())
+                    .add_messages(msgs));
+            }
+
+            // the outbid proxy stays as a bid of its whole escrow
+            listing.max_bid = proxy_max;
+            listing.proxy_max = None;
+            msgs.extend(push_prior_bid(&mut listing, &env, &sender)?);
+        },
+        None => {
+            // check if current bid exceeds the previous one and keep it as fallback
+            if env.contract.address != listing.max_bidder {
+                if is_below_next_bid(deps.as_ref(), &bid_price, &listing.max_bid, increment)? {
+                    return Err(ContractError::InsufficientFundsSend{});
+                }
+                msgs.extend(push_prior_bid(&mut listing, &env, &sender)?);
+            }
+        },
+    }
+
+    // update bidder
+    listing.max_bidder = sender;
+    listing.max_bid = bid_price;
+    listing.max_bid_expires = expires;
+    listing.max_bid_referrer = referrer;
+    list_resolver(deps.storage).save(key, &listing)?;
+
+    Ok(Response::new()
+        .add_attribute("Bidding", listing_id)
+        .add_messages(msgs))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_proxy_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+    max_bid: Asset,
+    referrer: Option<String>,
+    proof: Option<Vec<String>>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    // the whole maximum is escrowed
+    max_bid.assert_sent_native_token_balance(&info)?;
+    let referrer = validate_referrer(deps.as_ref(), referrer, &sender)?;
+
+    let key = listing_id.as_bytes();
+    let (mut listing, minimum) = load_biddable_listing(deps.as_ref(), &env, &listing_id, &max_bid)?;
+    assert_bidder_access(deps.as_ref(), &listing, &sender, proof)?;
+    let increment = read_config(deps.storage)?.min_bid_increment;
+    let mut msgs = drop_expired_bid(&mut listing, &env)?;
+
+    if env.contract.address == listing.max_bidder {
+        // the first bid opens at the minimum of its asset
+        listing.max_bid = Asset { info: max_bid.info.clone(), amount: minimum };
+    } else {
+        // proxies bid in the asset of the top bid
+        if max_bid.info != listing.max_bid.info {
+            return Err(ContractError::AssetInfoMismatch{});
+        }
+
+        // what the leader committed, the current bid when it is no proxy
+        let leader_max = listing.proxy_max.clone().unwrap_or_else(|| listing.max_bid.clone());
+
+        if max_bid.amount >= next_bid(leader_max.amount, increment) {
+            let price = if sender != listing.max_bidder {
+                proxy_price(leader_max.amount, max_bid.amount, increment)
+            } else {
+                listing.max_bid.amount
+            };
+            // the outbid leader stays as a bid of what it committed
+            listing.max_bid = leader_max;
+            listing.proxy_max = None;
+            msgs.extend(push_prior_bid(&mut listing, &env, &sender)?);
+            listing.max_bid = Asset { info: max_bid.info.clone(), amount: price };
+        } else if listing.proxy_max.is_some() && max_bid.amount >= next_bid(listing.max_bid.amount, increment) {
+            // the earlier proxy keeps the lead, also on ties
+            listing.max_bid.amount = proxy_price(max_bid.amount, leader_max.amount, increment);
+            list_resolver(deps.storage).save(key, &listing)?;
+
+            msgs.push(max_bid.into_msg(sender)?);
+            return Ok(Response::new()
+                .add_attribute("Bidding", listing_id)
+                .add_attribute("outbid_by_proxy", listing.max_bid.to_string())
+                .add_messages(msgs));
+        } else {
+            return Err(ContractError::InsufficientFundsSend{});
+        }
+    }
+
+    listing.max_bidder = sender;
+    listing.proxy_max = Some(max_bid);
+    listing.max_bid_expires = None;
+    listing.max_bid_referrer = referrer;
+    list_resolver(deps.storage).save(key, &listing)?;
+
+    // the maximum stays private, only the bid it currently pays is public
+    Ok(Response::new()
+        .add_attribute("Bidding", listing_id)
+        .add_attribute("bid", listing.max_bid.to_string())
+        .add_messages(msgs))
+}
+
+// Loads a running listing and checks the bid asset is accepted and above its minimum,
+// which is returned along the listing
+fn load_biddable_listing(
+    deps: Deps,
+    env: &Env,
+    listing_id: &str,
+    bid: &Asset,
+) -> Result<(Listing, Uint128), ContractError> {
+    let listing = list_resolver_read(deps.storage).load(listing_id.as_bytes())?;
+
+    if listing.items.iter().any(|item| !item.deposited) {
+        return Err(ContractError::BundleNotDeposited {});
+    }
+
+    if listing.block_limit < env.block.height {
+        return Err(ContractError::AuctionEnded {});
+    }
+
+    assert_payment_asset(deps.storage, &bid.info)?;
+
+    // check if the token type is one of the listing and above its minimum
+    let minimum = listing.accepted_assets.iter()
+        .find(|asset| asset.info == bid.info)
+        .ok_or(ContractError::AssetInfoMismatch{})?
+        .amount;
+
+    if bid.amount < minimum {
+        return Err(ContractError::InsufficientFundsSend{});
+    }
+
+    Ok((listing, minimum))
+}
+
+// Invite-only listings take bids from the bidders admitted by their access rule only
+fn assert_bidder_access(
+    deps: Deps,
+    listing: &Listing,
+    bidder: &Addr,
+    proof: Option<Vec<String>>,
+) -> Result<(), ContractError> {
+    let allowed = match &listing.access {
+        None => true,
+        Some(AccessRule::Addresses { addresses }) => addresses.iter().any(|address| address == bidder.as_str()),
+        // a malformed proof does not admit the bidder either
+        Some(AccessRule::MerkleRoot { root }) => verify_proof(root, bidder.as_bytes(), &proof.unwrap_or_default()).unwrap_or(false),
+        Some(AccessRule::TokenHolder { collection }) => {
+            let held: TokensResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: collection.clone(),
+                msg: to_binary(&Cw721QueryMsg::Tokens { owner: bidder.to_string(), start_after: None, limit: Some(1) })?,
+            }))?;
+            !held.tokens.is_empty()
+        },
+    };
+
+    if !allowed {
+        return Err(ContractError::BidderNotAllowed {});
+    }
+
+    Ok(())
+}
+
+fn validate_access_rule(api: &dyn Api, access: &Option<AccessRule>) -> Result<(), ContractError> {
+    match access {
+        Some(AccessRule::Addresses { addresses }) => {
+            for address in addresses.iter() {
+                api.addr_validate(address)?;
+            }
+        },
+        Some(AccessRule::MerkleRoot { root }) => {
+            decode_hash(root).map_err(|_| ContractError::InvalidMerkleRoot {})?;
+        },
+        Some(AccessRule::TokenHolder { collection }) => {
+            api.addr_validate(collection)?;
+        },
+        None => {},
+    }
+
+    Ok(())
+}
+
+// the lowest amount outbidding the given one
+fn next_bid(amount: Uint128, increment: Decimal) -> Uint128 {
+    amount + (amount * increment).max(Uint128::from(1u128))
+}
+
+// a proxy leads by the minimum increment, never above its maximum
+fn proxy_price(competing: Uint128, proxy_max: Uint128, increment: Decimal) -> Uint128 {
+    next_bid(competing, increment).min(proxy_max)
+}
+
+// Keeps the bid being outbid escrowed as fallback, a bidder raising its own bid is refunded instead
+fn push_prior_bid(listing: &mut Listing, env: &Env, new_bidder: &Addr) -> StdResult<Vec<CosmosMsg>> {
+    if env.contract.address == listing.max_bidder {
+        return Ok(vec![]);
+    }
+    if *new_bidder == listing.max_bidder {
+        return Ok(vec![listing.max_bid.clone().into_msg(listing.max_bidder.clone())?]);
+    }
+
+    listing.prior_bids.push(Bid {
+        bidder: listing.max_bidder.clone(),
+        amount: listing.max_bid.clone(),
+        expires: listing.max_bid_expires,
+        referrer: listing.max_bid_referrer.clone(),
+    });
+
+    // the oldest fallback is the least likely to lead again
+    if listing.prior_bids.len() > MAX_PRIOR_BIDS {
+        let oldest = listing.prior_bids.remove(0);
+        return Ok(vec![oldest.amount.into_msg(oldest.bidder)?]);
+    }
+    Ok(vec![])
+}
+
+// Refunds the max bid once expired and restores the best prior bid in its place
+fn drop_expired_bid(listing: &mut Listing, env: &Env) -> StdResult<Vec<CosmosMsg>> {
+    if env.contract.address == listing.max_bidder
+        || !listing.max_bid_expires.is_some_and(|expires| expires.is_expired(&env.block)) {
+        return Ok(vec![]);
+    }
+
+    // proxies never expire, the max bid is the whole escrow
+    let mut msgs = vec![listing.max_bid.clone().into_msg(listing.max_bidder.clone())?];
+    msgs.extend(restore_prior_bid(listing, env)?);
+    Ok(msgs)
+}
+
+// Makes the latest prior bid still valid the max bid, refunding the expired ones on the way
+fn restore_prior_bid(listing: &mut Listing, env: &Env) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = vec![];
+    listing.proxy_max = None;
+    while let Some(bid) = listing.prior_bids.pop() {
+        if bid.expires.is_some_and(|expires| expires.is_expired(&env.block)) {
+            msgs.push(bid.amount.into_msg(bid.bidder)?);
+            continue;
+        }
+        listing.max_bidder = bid.bidder;
+        listing.max_bid = bid.amount;
+        listing.max_bid_expires = bid.expires;
+        listing.max_bid_referrer = bid.referrer;
+        return Ok(msgs);
+    }
+
+    // no bid left, the listing is back at its minimum
+    listing.max_bidder = env.contract.address.clone();
+    listing.max_bid = listing.accepted_assets[0].clone();
+    listing.max_bid_expires = None;
+    listing.max_bid_referrer = None;
+    Ok(msgs)
+}
+
+pub fn execute_retract_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+) -> Result<Response, ContractError> {
+    let key = listing_id.as_bytes();
+    let mut listing = list_resolver_read(deps.storage).load(key)?;
+
+    if listing.block_limit < env.block.height {
+        return Err(ContractError::AuctionEnded {});
+    }
+
+    let mut msgs = drop_expired_bid(&mut listing, &env)?;
+
+    let (own_bids, prior_bids): (Vec<Bid>, Vec<Bid>) = listing.prior_bids.into_iter()
+        .partition(|bid| bid.bidder == info.sender);
+    listing.prior_bids = prior_bids;
+
+    let is_leader = info.sender == listing.max_bidder;
+    if !is_leader && own_bids.is_empty() {
+        return Err(ContractError::NoBidToRetract {});
+    }
+
+    let rules = listing.retraction.clone().ok_or(ContractError::RetractionNotAllowed {})?;
+    if listing.block_limit - env.block.height <= rules.min_blocks_before_end {
+        return Err(ContractError::RetractionNotAllowed {});
+    }
+
+    // outbid bids can lead again once the bids above them are gone, so they pay the penalty too
+    for bid in own_bids {
+        let penalty = bid.amount.amount * rules.penalty_rate;
+        msgs.extend(retraction_msgs(&listing.seller, &bid.bidder, bid.amount, penalty)?);
+    }
+
+    if is_leader {
+        // the penalty is on the bid, the rest of a proxy escrow is refunded in full
+        let escrow = listing.proxy_max.clone().unwrap_or_else(|| listing.max_bid.clone());
+        let penalty = listing.max_bid.amount * rules.penalty_rate;
+        msgs.extend(retraction_msgs(&listing.seller, &info.sender, escrow, penalty)?);
+        msgs.extend(restore_prior_bid(&mut listing, &env)?);
+    }
+
+    list_resolver(deps.storage).save(key, &listing)?;
+
+    Ok(Response::new()
+        .add_attribute("retract_bid", listing_id)
+        .add_attribute("bid", listing.max_bid.to_string())
+        .add_messages(msgs))
+}
+
+// pays the penalty of a retracted bid to the seller and refunds the rest of its escrow
+fn retraction_msgs(seller: &Addr, bidder: &Addr, escrow: Asset, penalty: Uint128) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut msgs = vec![];
+    if !penalty.is_zero() {
+        msgs.push(Asset { info: escrow.info.clone(), amount: penalty }.into_msg(seller.clone())?);
+    }
+    let refund = escrow.amount.checked_sub(penalty)?;
+    if !refund.is_zero() {
+        msgs.push(Asset { info: escrow.info, amount: refund }.into_msg(bidder.clone())?);
+    }
+    Ok(msgs)
+}
+
+// bids in different assets are compared in the quote unit of the price source
+fn is_below_next_bid(deps: Deps, bid: &Asset, max_bid: &Asset, increment: Decimal) -> Result<bool, ContractError> {
+    if bid.info == max_bid.info {
+        return Ok(bid.amount < next_bid(max_bid.amount, increment));
+    }
+
+    let source = read_price_source(deps.storage)?.ok_or(ContractError::PriceSourceNotSet {})?;
+    Ok(asset_value(deps, &source, bid)? < next_bid(asset_value(deps, &source, max_bid)?, increment))
+}
+
+fn asset_value(deps: Deps, source: &PriceSource, asset: &Asset) -> Result<Uint128, ContractError> {
+    let price = match source {
+        PriceSource::Fixed { prices } => prices.iter()
+            .find(|price| price.info == asset.info)
+            .map(|price| price.price)
+            .ok_or(ContractError::AssetNotPriced { asset: asset.info.to_string() })?,
+        PriceSource::Oracle { contract } => {
+            let res: PriceResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: contract.to_string(),
+                msg: to_binary(&OracleQueryMsg::Price { asset_info: asset.info.clone() })?,
+            }))?;
+            res.price
+        }
+    };
+
+    Ok(asset.amount * price)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_place_listing(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    id: String,
+    minimum_bid: Asset,
+    escrow: bool,
+    nft_contract: Option<String>,
+    other_assets: Vec<Asset>,
+    retraction: Option<RetractionRules>,
+    payouts: Vec<PayoutShare>,
+    access: Option<AccessRule>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let nft_contract_address = match nft_contract {
+        Some(nft_contract) => deps.api.addr_validate(&nft_contract)?,
+        None => read_nft_address(deps.storage)?,
+    };
+
+    // the token is pulled with the operator approval of its owner, so only the owner can list it
+    let token_owner: OwnerOfResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: nft_contract_address.to_string(),
+        msg: to_binary(&Cw721QueryMsg::OwnerOf { token_id: id.clone(), include_expired: None })?,
+    }))?;
+    if token_owner.owner != sender {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let config_state = read_config(deps.storage)?;
+    let items = vec![ListingItem::new(nft_contract_address.clone(), id.clone())];
+    store_listing(deps, &env, items, minimum_bid, other_assets, escrow, retraction, payouts, access, sender)?;
+
+    // lock nft to contract
+    Ok(Response::new()
+        .add_attribute("place_listing", id.to_string())
+        .add_messages(vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: nft_contract_address.to_string(),
+                funds: vec![],
+                msg: to_binary(&Approve {
+                    spender: env.contract.address.to_string(),
+                    token_id: id.clone(),
+                    expires: Some(Expiration::AtHeight(env.block.height + config_state.max_aution_duration_blocks)),
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: nft_contract_address.to_string(),
+                funds: vec![],
+                msg: to_binary(&TransferNft {
+                    recipient: String::from(env.contract.address.as_str()),
+                    token_id: id,
+                })?,
+            }),
+        ]))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_place_bundle(
+    deps: DepsMut,
+    env: Env,
+    items: Vec<BundleItemMsg>,
+    minimum_bid: Asset,
+    escrow: bool,
+    other_assets: Vec<Asset>,
+    retraction: Option<RetractionRules>,
+    payouts: Vec<PayoutShare>,
+    access: Option<AccessRule>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    if items.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if items.len() as u64 > config.max_batch_size {
+        return Err(ContractError::BatchSizeExceeded { max: config.max_batch_size });
+    }
+
+    let mut listing_items: Vec<ListingItem> = vec![];
+    for item in items {
+        let contract_addr = deps.api.addr_validate(&item.collection)?;
+        if listing_items.iter().any(|other| other.contract_addr == contract_addr && other.token_id == item.token_id) {
+            return Err(ContractError::DuplicateBundleItem { token_id: item.token_id });
+        }
+
+        let weight = item.weight.unwrap_or(1);
+        if weight == 0 {
+            return Err(ContractError::InvalidItemWeight {});
+        }
+
+        // the seller deposits the tokens before the auction opens
+        listing_items.push(ListingItem { contract_addr, token_id: item.token_id, weight, deposited: false });
+    }
+
+    let listing_id = store_listing(deps, &env, listing_items, minimum_bid, other_assets, escrow, retraction, payouts,
+        access, sender)?;
+
+    Ok(Response::new()
+        .add_attribute("place_bundle", listing_id))
+}
+
+fn execute_deposit_bundle_item(
+    deps: DepsMut,
+    env: Env,
+    collection: Addr,
+    token_id: String,
+    listing_id: String,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let key = listing_id.as_bytes();
+    let mut listing = list_resolver_read(deps.storage).load(key)?;
+
+    if sender != listing.seller {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let item = listing.items.iter_mut()
+        .find(|item| !item.deposited && item.contract_addr == collection && item.token_id == token_id)
+        .ok_or(ContractError::NotABundleItem{})?;
+    item.deposited = true;
+
+    // the auction runs from the last deposit
+    let mut res = Response::new()
+        .add_attribute("deposit_bundle_item", listing_id.clone())
+        .add_attribute("token_id", token_id);
+    if listing.items.iter().all(|item| item.deposited) {
+        let config = read_config(deps.storage)?;
+        listing.block_limit = env.block.height + config.max_aution_duration_blocks;
+        store_listing_end(deps.storage, &listing_id, listing.block_limit)?;
+        res = res.add_attribute("auction_opened", listing_id.clone());
+    }
+    list_resolver(deps.storage).save(key, &listing)?;
+
+    Ok(res)
+}
+
+// saves a new listing and returns its id
+#[allow(clippy::too_many_arguments)]
+fn store_listing(
+    deps: DepsMut,
+    env: &Env,
+    items: Vec<ListingItem>,
+    minimum_bid: Asset,
+    other_assets: Vec<Asset>,
+    escrow: bool,
+    retraction: Option<RetractionRules>,
+    payouts: Vec<PayoutShare>,
+    access: Option<AccessRule>,
+    seller: Addr,
+) -> Result<String, ContractError> {
+    // unknown cw721 contracts could report tokens they never held
+    for item in &items {
+        if !is_collection_allowed(deps.storage, &item.contract_addr)? {
+            return Err(ContractError::CollectionNotAllowed { collection: item.contract_addr.to_string() });
+        }
+    }
+
+    // the asset of the minimum bid is always accepted
+    let mut accepted_assets = vec![minimum_bid.clone()];
+    assert_payment_asset(deps.storage, &minimum_bid.info)?;
+    for asset in other_assets {
+        assert_payment_asset(deps.storage, &asset.info)?;
+        if accepted_assets.iter().any(|accepted| accepted.info == asset.info) {
+            return Err(ContractError::DuplicateAsset { asset: asset.info.to_string() });
+        }
+        accepted_assets.push(asset);
+    }
+
+    if retraction.as_ref().is_some_and(|rules| rules.penalty_rate > Decimal::one()) {
+        return Err(ContractError::InvalidPenaltyRate {});
+    }
+
+    validate_payouts(deps.api, &payouts)?;
+    validate_access_rule(deps.api, &access)?;
+
+    // update listing id in store
+    let mut config_state = read_config(deps.storage)?;
+    config_state.listing_count += 1;
+    store_config(deps.storage, &config_state)?;
+    let listing_count = config_state.listing_count;
+
+    // Each auction has a limit for 50000 blocks, bundles only start once complete
+    let block_limit = if items.iter().all(|item| item.deposited) {
+        env.block.height + config_state.max_aution_duration_blocks
+    } else {
+        0
+    };
+    let listing = Listing {
+        items,
+        seller,
+        max_bid: minimum_bid,
+        max_bidder: env.contract.address.clone(),
+        block_limit,
+        escrow,
+        accepted_assets,
+        proxy_max: None,
+        max_bid_expires: None,
+        max_bid_referrer: None,
+        prior_bids: vec![],
+        retraction,
+        payouts,
+        access,
+    };
+
+    let key = listing_count.to_string();
+    // save listing to store
+    list_resolver(deps.storage).save(key.as_bytes(), &listing)?;
+    if block_limit != 0 {
+        store_listing_end(deps.storage, &key, block_limit)?;
+    }
+
+    Ok(key)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_make_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    token_id: String,
+    price: Asset,
+    expires: Expiration,
+    referrer: Option<String>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    // the offered amount is escrowed
+    price.assert_sent_native_token_balance(&info)?;
+    assert_payment_asset(deps.storage, &price.info)?;
+    let referrer = validate_referrer(deps.as_ref(), referrer, &sender)?;
+
+    if price.amount.is_zero() {
+        return Err(ContractError::InsufficientFundsSend{});
+    }
+
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::OfferExpired{});
+    }
+
+    let mut config = read_config(deps.storage)?;
+    config.offer_count += 1;
+    store_config(deps.storage, &config)?;
+
+    let offer = Offer {
+        id: config.offer_count,
+        collection: deps.api.addr_validate(&collection)?,
+        token_id,
+        bidder: sender,
+        price,
+        expires,
+        referrer,
+    };
+    store_offer(deps.storage, &offer)?;
+
+    Ok(Response::new()
+        .add_attribute("make_offer", offer.id.to_string())
+        .add_attribute("token_id", offer.token_id))
+}
+
+pub fn execute_cancel_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = read_offer(deps.storage, offer_id)?;
+
+    // expired offers can be cleaned up by anybody, the funds go back to the bidder anyway
+    if info.sender != offer.bidder && !offer.expires.is_expired(&env.block) {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    remove_offer(deps.storage, &offer);
+
+    Ok(Response::new()
+        .add_attribute("cancel_offer", offer_id.to_string())
+        .add_message(offer.price.into_msg(offer.bidder)?))
+}
+
+fn execute_accept_offer(
+    deps: DepsMut,
+    env: Env,
+    collection: Addr,
+    token_id: String,
+    offer_id: u64,
+    seller: Addr,
+) -> Result<Response, ContractError> {
+    let offer = read_offer(deps.storage, offer_id)?;
+
+    if offer.collection != collection || offer.token_id != token_id {
+        return Err(ContractError::OfferMismatch{});
+    }
+
+    if offer.expires.is_expired(&env.block) {
+        return Err(ContractError::OfferExpired{});
+    }
+
+    remove_offer(deps.storage, &offer);
+    mark_asset_sold(deps.storage, &collection, &token_id)?;
+
+    let mut deps = deps;
+    let items = [ListingItem::new(collection.clone(), token_id.clone())];
+    let msgs = release_sale_msgs(deps.branch(), &items, &offer.price, &[(seller, Decimal::one())], &offer.bidder,
+        None, offer.referrer.as_ref())?;
+
+    // the token is sold, the other offers on it are refunded up to the batch size
+    let max_id = read_config(deps.storage)?.offer_count;
+    let (refund_msgs, pending) = refund_token_offers(deps.storage, &collection, &token_id, max_id)?;
+
+    let mut res = Response::new()
+        .add_attribute("accept_offer", offer_id.to_string())
+        .add_attribute("token_id", token_id)
+        .add_messages(msgs)
+        .add_messages(refund_msgs);
+    if pending {
+        res = res.add_attribute("offers_pending_refund", "true");
+    }
+    Ok(res)
+}
+
+// refunds a batch of the offers on a token up to max_id, remembering the rest for RefundOffers
+fn refund_token_offers(
+    storage: &mut dyn Storage,
+    collection: &Addr,
+    token_id: &str,
+    max_id: u64,
+) -> Result<(Vec<CosmosMsg>, bool), ContractError> {
+    let limit = read_config(storage)?.max_batch_size as usize;
+    let ids = read_token_offer_ids(storage, collection, token_id, max_id, limit + 1)?;
+    let pending = ids.len() > limit;
+
+    let mut msgs = vec![];
+    for id in ids.into_iter().take(limit) {
+        let offer = read_offer(storage, id)?;
+        remove_offer(storage, &offer);
+        msgs.push(offer.price.into_msg(offer.bidder)?);
+    }
+
+    if pending {
+        OFFER_REFUNDS.save(storage, (collection, token_id), &max_id)?;
+    } else {
+        OFFER_REFUNDS.remove(storage, (collection, token_id));
+    }
+    Ok((msgs, pending))
+}
+
+pub fn execute_refund_offers(
+    deps: DepsMut,
+    collection: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let collection = deps.api.addr_validate(&collection)?;
+    let max_id = OFFER_REFUNDS.may_load(deps.storage, (&collection, &token_id))?
+        .ok_or(ContractError::NoOffersToRefund{})?;
+
+    // anybody can continue, the funds only go back to the bidders
+    let (msgs, pending) = refund_token_offers(deps.storage, &collection, &token_id, max_id)?;
+
+    let mut res = Response::new()
+        .add_attribute("refund_offers", token_id)
+        .add_messages(msgs);
+    if pending {
+        res = res.add_attribute("offers_pending_refund", "true");
+    }
+    Ok(res)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_make_collection_offer(
+    deps: DepsMut,
+    env: Env,
+    collection: String,
+    escrow: Asset,
+    quantity: u64,
+    traits: Option<OfferTraits>,
+    expires: Expiration,
+    referrer: Option<String>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    assert_payment_asset(deps.storage, &escrow.info)?;
+    let referrer = validate_referrer(deps.as_ref(), referrer, &sender)?;
+
+    // every token is paid the same share of the escrow
+    let quantity_amount = Uint128::from(quantity);
+    if quantity == 0 || escrow.amount.is_zero() || !escrow.amount.u128().is_multiple_of(quantity as u128) {
+        return Err(ContractError::InvalidOfferQuantity{});
+    }
+
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::OfferExpired{});
+    }
+
+    let mut config = read_config(deps.storage)?;
+    config.offer_count += 1;
+    store_config(deps.storage, &config)?;
+
+    let offer = CollectionOffer {
+        id: config.offer_count,
+        collection: deps.api.addr_validate(&collection)?,
+        bidder: sender,
+        price: Asset { info: escrow.info, amount: escrow.amount / quantity_amount },
+        quantity,
+        traits,
+        expires,
+        referrer,
+    };
+    store_collection_offer(deps.storage, &offer)?;
+
+    Ok(Response::new()
+        .add_attribute("make_collection_offer", offer.id.to_string())
+        .add_attribute("collection", offer.collection))
+}
+
+pub fn execute_cancel_collection_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = read_collection_offer(deps.storage, offer_id)?;
+
+    // the remainder of expired offers can be refunded by anybody
+    if info.sender != offer.bidder && !offer.expires.is_expired(&env.block) {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    remove_collection_offer(deps.storage, &offer);
+
+    let refund = Asset {
+        info: offer.price.info,
+        amount: offer.price.amount.checked_mul(Uint128::from(offer.quantity))?,
+    };
+
+    Ok(Response::new()
+        .add_attribute("cancel_collection_offer", offer_id.to_string())
+        .add_message(refund.into_msg(offer.bidder)?))
+}
+
+fn execute_fill_collection_offer(
+    deps: DepsMut,
+    env: Env,
+    collection: Addr,
+    token_id: String,
+    offer_id: u64,
+    seller: Addr,
+) -> Result<Response, ContractError> {
+    let mut offer = read_collection_offer(deps.storage, offer_id)?;
+
+    if offer.collection != collection {
+        return Err(ContractError::OfferMismatch{});
+    }
+
+    if offer.expires.is_expired(&env.block) {
+        return Err(ContractError::OfferExpired{});
+    }
+
+    if let Some(traits) = &offer.traits {
+        let metadata = query_token_metadata(deps.as_ref(), &collection, &token_id)
+            .map_err(|_| ContractError::TraitMismatch{})?;
+
+        if traits.collection_id.is_some_and(|id| metadata.collection != Some(id))
+            || traits.num_real_repr.is_some_and(|num| metadata.num_real_repr != num) {
+            return Err(ContractError::TraitMismatch{});
+        }
+    }
+
+    // partial fills keep the offer open for the remaining quantity
+    offer.quantity -= 1;
+    if offer.quantity == 0 {
+        remove_collection_offer(deps.storage, &offer);
+    } else {
+        store_collection_offer(deps.storage, &offer)?;
+    }
+    mark_asset_sold(deps.storage, &collection, &token_id)?;
+
+    let items = [ListingItem::new(collection, token_id.clone())];
+    let msgs = release_sale_msgs(deps, &items, &offer.price, &[(seller, Decimal::one())], &offer.bidder,
+        None, offer.referrer.as_ref())?;
+
+    Ok(Response::new()
+        .add_attribute("fill_collection_offer", offer_id.to_string())
+        .add_attribute("token_id", token_id)
+        .add_attribute("remaining", offer.quantity.to_string())
+        .add_messages(msgs))
+}
+
+pub fn execute_withdraw_listing(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+) -> Result<Response, ContractError> {
+
+    let listing = list_resolver_read(deps.storage).load(listing_id.as_bytes())?;
+
+    // an incomplete bundle never opened, only its seller can take the deposited items back
+    let pending = listing.items.iter().any(|item| !item.deposited);
+    if pending && info.sender != listing.seller {
+        return Err(ContractError::BundleNotDeposited {});
+    }
+
+    settle_listing(deps, &env, listing_id, listing, info.sender)
+}
+
+pub fn execute_batch_settle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    if listing_ids.len() as u64 > config.max_batch_size {
+        return Err(ContractError::BatchSizeExceeded { max: config.max_batch_size });
+    }
+
+    // keepers race each other, listings already settled or not ready are skipped
+    let mut deps = deps;
+    let mut res = Response::new();
+    for listing_id in listing_ids {
+        let listing = match list_resolver_read(deps.storage).may_load(listing_id.as_bytes())? {
+            Some(listing) if is_settleable(&listing, &env) => listing,
+            _ => continue,
+        };
+
+        // a listing failing to settle does not hold back the others
+        match settle_listing(deps.branch(), &env, listing_id.clone(), listing, info.sender.clone()) {
+            Ok(settled) => res = res.add_attributes(settled.attributes).add_submessages(settled.messages),
+            Err(_) => res = res.add_attribute("settle_failed", listing_id),
+        }
+    }
+
+    Ok(res)
+}
+
+// an auction that ended with all of its items deposited
+fn is_settleable(listing: &Listing, env: &Env) -> bool {
+    listing.block_limit < env.block.height && listing.items.iter().all(|item| item.deposited)
+}
+
+fn settle_listing(
+    deps: DepsMut,
+    env: &Env,
+    listing_id: String,
+    listing: Listing,
+    keeper: Addr,
+) -> Result<Response, ContractError> {
+    let mut listing = listing;
+
+    // Check if the auction ended or not
+    if listing.block_limit >= env.block.height {
+        return Err(ContractError::AuctionNotEnded {});
+    }
+
+    // whatever can fail runs before the listing is removed, so a batch can skip it cleanly
+
+    // the winning bid must still be valid, the outbid ones are refunded
+    let mut refund_msgs = drop_expired_bid(&mut listing, env)?;
+    for bid in listing.prior_bids.drain(..) {
+        refund_msgs.push(bid.amount.into_msg(bid.bidder)?);
+    }
+
+    // If noone has put a bid then then seller will be sent back with his NFT
+    // Transfer the locked NFT to highest bidder and bid amount to the seller
+    if env.contract.address != listing.max_bidder {
+        // listings accept several assets, record the one that won
+        let winning_bid = listing.max_bid.to_string();
+
+        // unused escrow of a winning proxy goes back to the bidder
+        if let Some(proxy_max) = &listing.proxy_max {
+            let unused = proxy_max.amount.checked_sub(listing.max_bid.amount)?;
+            if !unused.is_zero() {
+                refund_msgs.push(Asset { info: proxy_max.info.clone(), amount: unused }
+                    .into_msg(listing.max_bidder.clone())?);
+            }
+        }
+
+        // the assets are read up front, nothing is written before the last fallible step
+        let mut sold_assets = vec![];
+        for item in listing.items.iter() {
+            sold_assets.extend(unsold_asset(deps.storage, &item.contract_addr, &item.token_id)?);
+        }
+
+        // physical item sales hold the nft and the proceeds until the delivery is settled
+        if listing.escrow {
+            let config = read_config(deps.storage)?;
+            let sale = EscrowedSale {
+                listing_id: listing_id.clone(),
+                items: listing.items,
+                seller: listing.seller,
+                buyer: listing.max_bidder,
+                price: listing.max_bid,
+                keeper,
+                referrer: listing.max_bid_referrer,
+                payouts: listing.payouts,
+                status: EscrowStatus::Pending,
+                dispute_deadline: env.block.time.seconds() + config.dispute_window,
+                dispute_reason: None,
+            };
+            list_resolver(deps.storage).remove(listing_id.as_bytes());
+            remove_listing_end(deps.storage, &listing_id, listing.block_limit);
+            store_sold_assets(deps.storage, sold_assets)?;
+            store_escrowed_sale(deps.storage, &sale)?;
+
+            return Ok(Response::new()
+                .add_attribute("listing_escrowed", listing_id)
+                .add_attribute("winning_bid", winning_bid)
+                .add_messages(refund_msgs));
+        }
+
+        let proceeds = proceeds_recipients(deps.api, &listing.seller, &listing.payouts)?;
+        let mut msgs = transfer_items_msgs(&listing.items, &listing.max_bidder)?;
+        let (payout_msgs, referrer_record) = listing_payout(
+            deps.as_ref(),
+            &listing.items,
+            &listing.max_bid,
+            &proceeds,
+            Some(&keeper),
+            listing.max_bid_referrer.as_ref(),
+        )?;
+        msgs.extend(payout_msgs);
+
+        list_resolver(deps.storage).remove(listing_id.as_bytes());
+        remove_listing_end(deps.storage, &listing_id, listing.block_limit);
+        store_sold_assets(deps.storage, sold_assets)?;
+        if let Some(record) = referrer_record {
+            store_referrer(deps.storage, &record)?;
+        }
+
+        Ok(Response::new()
+            .add_attribute("listing_sold", listing_id.to_string())
+            .add_attribute("winning_bid", winning_bid)
+            .add_messages(msgs)
+            .add_messages(refund_msgs))
+    } else {
+        let deposited: Vec<ListingItem> = listing.items.into_iter().filter(|item| item.deposited).collect();
+        let return_msgs = transfer_items_msgs(&deposited, &listing.seller)?;
+        list_resolver(deps.storage).remove(listing_id.as_bytes());
+        remove_listing_end(deps.storage, &listing_id, listing.block_limit);
+        Ok(Response::new()
+            .add_attribute("listing_unsold", listing_id.to_string())
+            .add_messages(return_msgs)
+            .add_messages(refund_msgs))
+    }
+}
+
+fn store_sold_assets(storage: &mut dyn Storage, assets: Vec<NftAsset>) -> StdResult<()> {
+    for mut asset in assets {
+        asset.sold = true;
+        store_nft_asset(storage, &asset)?;
+    }
+    Ok(())
+}
+
+// transfer the nft to the buyer and the price, minus royalties, to the seller
+fn release_sale_msgs(
+    deps: DepsMut,
+    items: &[ListingItem],
+    price: &Asset,
+    proceeds: &[(Addr, Decimal)],
+    buyer: &Addr,
+    keeper: Option<&Addr>,
+    referrer: Option<&Addr>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut msgs = transfer_items_msgs(items, buyer)?;
+
+    msgs.extend(listing_payout_msgs(deps, items, price, proceeds, keeper, referrer)?);
+
+    Ok(msgs)
+}
+
+fn transfer_items_msgs(items: &[ListingItem], recipient: &Addr) -> StdResult<Vec<CosmosMsg>> {
+    items.iter()
+        .map(|item| Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: item.contract_addr.to_string(),
+            funds: vec![],
+            msg: to_binary(&TransferNft {
+                recipient: recipient.to_string(),
+                token_id: item.token_id.clone(),
+            })?,
+        })))
+        .collect()
+}
+
+fn listing_payout_msgs(
+    deps: DepsMut,
+    items: &[ListingItem],
+    price: &Asset,
+    proceeds: &[(Addr, Decimal)],
+    keeper: Option<&Addr>,
+    referrer: Option<&Addr>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let (msgs, referrer_record) = listing_payout(deps.as_ref(), items, price, proceeds, keeper, referrer)?;
+
+    // earnings are only recorded once the payout is final
+    if let Some(record) = referrer_record {
+        store_referrer(deps.storage, &record)?;
+    }
+
+    Ok(msgs)
+}
+
+// payout messages of a sale and the referrer record with its new earnings, left to the caller to save
+fn listing_payout(
+    deps: Deps,
+    items: &[ListingItem],
+    price: &Asset,
+    proceeds: &[(Addr, Decimal)],
+    keeper: Option<&Addr>,
+    referrer: Option<&Addr>,
+) -> Result<(Vec<CosmosMsg>, Option<Referrer>), ContractError> {
+    // distribute royalties and the protocol fee, the rest goes to the seller or its payout recipients
+    let mut split = PayoutSplit::new(price.amount);
+
+    // the royalties of each item are due on its weighted share of the price
+    let total_weight: u64 = items.iter().map(|item| item.weight).sum();
+    for item in items.iter() {
+        let item_price = price.amount.multiply_ratio(item.weight, total_weight);
+        let royalties = sale_royalties(deps, &item.contract_addr, &item.token_id, item_price)?;
+
+        for (recipient, amount) in apply_royalty_rules(deps, &item.contract_addr, &item.token_id, item_price, royalties)? {
+            split.add_amount(recipient, amount)?;
+        }
+    }
+
+    // the keeper settling the sale is paid out of the fee, never more than the royalties leave
+    let config = read_config(deps.storage)?;
+    let fee = (price.amount * config.protocol_fee_rate).min(price.amount.checked_sub(split.allocated())?);
+    let reward = match (keeper, &config.keeper_reward) {
+        (Some(_), Some(KeeperReward::FeeShare { rate })) => fee * *rate,
+        (Some(_), Some(KeeperReward::Fixed { amounts })) => amounts.iter()
+            .find(|amount| amount.info == price.info)
+            .map_or(Uint128::zero(), |amount| amount.amount.min(fee)),
+        _ => Uint128::zero(),
+    };
+    if let Some(keeper) = keeper {
+        split.add_amount(keeper.clone(), reward)?;
+    }
+
+    // referrers removed from the allowlist since the bid are not paid
+    let mut referral = Uint128::zero();
+    let mut referrer_record = None;
+    if let Some(referrer) = referrer {
+        let mut record = read_referrer(deps.storage, referrer)?;
+        if record.allowed {
+            referral = (fee * config.referral_rate).min(fee.checked_sub(reward)?);
+            add_earnings(&mut record.earnings, &Asset { info: price.info.clone(), amount: referral });
+            split.add_amount(referrer.clone(), referral)?;
+            referrer_record = Some(record);
+        }
+    }
+    split.add_amount(deps.api.addr_validate(&config.treasury)?, fee.checked_sub(reward)?.checked_sub(referral)?)?;
+    let msgs = payout_msgs(&price.info, split.finish_shared(proceeds)?)?;
+
+    Ok((msgs, referrer_record))
+}
+
+fn add_earnings(earnings: &mut Vec<Asset>, earned: &Asset) {
+    match earnings.iter_mut().find(|asset| asset.info == earned.info) {
+        Some(asset) => asset.amount += earned.amount,
+        None => earnings.push(earned.clone()),
+    }
+}
+
+// where the proceeds of a sale go, the seller unless payouts were given
+fn proceeds_recipients(api: &dyn Api, seller: &Addr, payouts: &[PayoutShare]) -> StdResult<Vec<(Addr, Decimal)>> {
+    if payouts.is_empty() {
+        return Ok(vec![(seller.clone(), Decimal::one())]);
+    }
+
+    payouts.iter()
+        .map(|payout| Ok((api.addr_validate(&payout.address)?, payout.share)))
+        .collect()
+}
+
+fn validate_payouts(api: &dyn Api, payouts: &[PayoutShare]) -> Result<(), ContractError> {
+    if payouts.len() > MAX_PAYOUT_RECIPIENTS {
+        return Err(ContractError::TooManyPayoutRecipients { max: MAX_PAYOUT_RECIPIENTS });
+    }
+
+    let mut recipients: Vec<Addr> = vec![];
+    for payout in payouts.iter() {
+        let recipient = api.addr_validate(&payout.address)?;
+        if payout.share.is_zero() || recipients.contains(&recipient) {
+            return Err(ContractError::InvalidPayoutShares {});
+        }
+        recipients.push(recipient);
+    }
+
+    // the shares must cover the proceeds exactly
+    let total = payouts.iter().fold(Decimal::zero(), |total, payout| total + payout.share);
+    if !payouts.is_empty() && total != Decimal::one() {
+        return Err(ContractError::InvalidPayoutShares {});
+    }
+
+    Ok(())
+}
+
+// Royalties owed on a sale. Collections implementing cw2981 are asked first, then the
+// royalties of our Metadata extension are used, then the ones registered by the owner
+fn sale_royalties(
+    deps: Deps,
+    contract_addr: &Addr,
+    token_id: &str,
+    sale_price: Uint128,
+) -> Result<Vec<(Addr, Uint128)>, ContractError> {
+    let check: Option<CheckRoyaltiesResponse> = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&Cw2981ExtensionQueryMsg::Extension { msg: Cw2981QueryMsg::CheckRoyalties {} })?,
+    })).ok();
+
+    if check.is_some_and(|check| check.royalty_payments) {
+        let info: RoyaltiesInfoResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw2981ExtensionQueryMsg::Extension {
+                msg: Cw2981QueryMsg::RoyaltyInfo { token_id: token_id.to_string(), sale_price },
+            })?,
+        }))?;
+
+        if info.royalty_amount > sale_price {
+            return Err(ContractError::InvalidRoyaltyRate {});
+        }
+        if info.royalty_amount.is_zero() {
+            return Ok(vec![]);
+        }
+        return Ok(vec![(deps.api.addr_validate(&info.address)?, info.royalty_amount)]);
+    }
+
+    // other extension types fail to parse as our Metadata
+    let royalties = match query_token_metadata(deps, contract_addr, token_id) {
+        Ok(metadata) => metadata.royalties,
+        Err(_) => read_registry_royalties(deps.storage, contract_addr)?,
+    };
+
+    royalties.iter()
+        .map(|royalty| Ok((deps.api.addr_validate(&royalty.address)?, sale_price * royalty.royalty_rate)))
+        .collect()
+}
+
+// Royalties above the configured cap are scaled down, and the share of a collection
+// creator goes to the payee they registered for the collection
+fn apply_royalty_rules(
+    deps: Deps,
+    contract_addr: &Addr,
+    token_id: &str,
+    sale_price: Uint128,
+    royalties: Vec<(Addr, Uint128)>,
+) -> Result<Vec<(Addr, Uint128)>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let max_amount = sale_price * config.max_royalty_rate;
+    let total: Uint128 = royalties.iter().map(|(_, amount)| *amount).sum();
+
+    let mut royalties = royalties;
+    if total > max_amount {
+        royalties = royalties.into_iter()
+            .map(|(recipient, amount)| (recipient, amount.multiply_ratio(max_amount, total)))
+            .collect();
+    }
+
+    // payees only exist for collections minted through this contract
+    if read_nft_address(deps.storage)? != *contract_addr {
+        return Ok(royalties);
+    }
+
+    let collection = match read_nft_asset(deps.storage, asset_id_of(token_id)).ok()
+        .and_then(|asset| asset.metadata.collection) {
+        Some(id) => read_collection(deps.storage, id)?,
+        None => return Ok(royalties),
+    };
+
+    Ok(match collection.royalty_payee.clone() {
+        Some(payee) => royalties.into_iter()
+            .map(|(recipient, amount)| if recipient == collection.creator { (payee.clone(), amount) } else { (recipient, amount) })
+            .collect(),
+        None => royalties,
+    })
+}
+
+pub fn execute_release_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+) -> Result<Response, ContractError> {
+    let sale = read_escrowed_sale(deps.storage, &listing_id)?;
+
+    if sale.status == EscrowStatus::Disputed {
+        return Err(ContractError::SaleDisputed {});
+    }
+
+    // the buyer confirms the delivery, otherwise anybody once the dispute window lapsed
+    if info.sender != sale.buyer && env.block.time.seconds() < sale.dispute_deadline {
+        return Err(ContractError::DisputeWindowOpen {});
+    }
+
+    remove_escrowed_sale(deps.storage, &listing_id);
+
+    let proceeds = proceeds_recipients(deps.api, &sale.seller, &sale.payouts)?;
+    let msgs = release_sale_msgs(
+        deps,
+        &sale.items,
+        &sale.price,
+        &proceeds,
+        &sale.buyer,
+        Some(&sale.keeper),
+        sale.referrer.as_ref(),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("escrow_released", listing_id)
+        .add_messages(msgs))
+}
+
+pub fn execute_open_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut sale = read_escrowed_sale(deps.storage, &listing_id)?;
+
+    if info.sender != sale.buyer && info.sender != sale.seller {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    if sale.status == EscrowStatus::Disputed {
+        return Err(ContractError::SaleDisputed {});
+    }
+
+    if env.block.time.seconds() >= sale.dispute_deadline {
+        return Err(ContractError::DisputeWindowClosed {});
+    }
+
+    sale.status = EscrowStatus::Disputed;
+    sale.dispute_reason = Some(reason);
+    store_escrowed_sale(deps.storage, &sale)?;
+
+    Ok(Response::new()
+        .add_attribute("dispute_opened", listing_id)
+        .add_attribute("by", info.sender))
+}
+
+pub fn execute_resolve_dispute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    listing_id: String,
+    resolution: DisputeResolution,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let arbiter = config.arbiter.ok_or(ContractError::ArbiterNotSet {})?;
+
+    if info.sender != deps.api.addr_validate(&arbiter)? {
+        return Err(ContractError::Unauthorized{});
+    }
+
+    let sale = read_escrowed_sale(deps.storage, &listing_id)?;
+
+    if sale.status != EscrowStatus::Disputed {
+        return Err(ContractError::NotDisputed {});
+    }
+
+    remove_escrowed_sale(deps.storage, &listing_id);
+
+    let proceeds = proceeds_recipients(deps.api, &sale.seller, &sale.payouts)?;
+    let msgs = match resolution {
+        DisputeResolution::ReleaseToSeller {} => release_sale_msgs(
+            deps,
+            &sale.items,
+            &sale.price,
+            &proceeds,
+            &sale.buyer,
+            Some(&sale.keeper),
+            sale.referrer.as_ref(),
+        )?,
+        // the buyer gets the money back and the seller the nft
+        DisputeResolution::RefundBuyer {} => {
+            let mut msgs = vec![sale.price.clone().into_msg(sale.buyer.clone())?];
+            msgs.extend(transfer_items_msgs(&sale.items, &sale.seller)?);
+            msgs
+        },
+        // the buyer keeps the nft with a partial refund, the rest is settled as a sale
+        DisputeResolution::Split { buyer_share } => {
+            if buyer_share > Decimal::one() {
+                return Err(ContractError::InvalidSplitShare {});
+            }
+
+            let refund = sale.price.amount * buyer_share;
+            let remainder = Asset {
+                info: sale.price.info.clone(),
+                amount: sale.price.amount.checked_sub(refund)?,
+            };
+
+            let mut msgs = transfer_items_msgs(&sale.items, &sale.buyer)?;
+
+            if !refund.is_zero() {
+                msgs.push((Asset { info: sale.price.info.clone(), amount: refund })
+                    .into_msg(sale.buyer.clone())?);
+            }
+
+            if !remainder.amount.is_zero() {
+                msgs.extend(listing_payout_msgs(deps, &sale.items, &remainder, &proceeds,
+                    Some(&sale.keeper), sale.referrer.as_ref())?);
+            }
+
+            msgs
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("dispute_resolved", listing_id)
+        .add_messages(msgs))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&read_config(deps.storage)?),
+        QueryMsg::ResolveListing { id } => query_list_resolver(deps, env, id),
+        QueryMsg::QueryMinter {} => to_binary(&query_minters(deps, env)?),
+        QueryMsg::QueryNftInfo {token_id} => to_binary(&query_nft_info(deps, env, token_id)?),
+        QueryMsg::AllTokens{} => to_binary(&query_all_nft_ids(deps, env)?),
+        QueryMsg::Editions{ asset_id } => to_binary(&query_editions(deps, env, asset_id)?),
+        QueryMsg::Collection{ id } => to_binary(&read_collection(deps.storage, id)?),
+        QueryMsg::Collections{ start_after, limit }
+            => to_binary(&query_collections(deps, env, start_after, limit)?),
+        QueryMsg::EscrowedSale{ listing_id } => to_binary(&read_escrowed_sale(deps.storage, &listing_id)?),
+        QueryMsg::Redemption{ redemption_id } => to_binary(&read_redemption(deps.storage, redemption_id)?),
+        QueryMsg::Redemptions{ holder, asset_id, start_after, limit }
+            => to_binary(&query_redemptions(deps, env, holder, asset_id, start_after, limit)?),
+        QueryMsg::RedeemablePieces{ asset_id } => to_binary(&query_redeemable_pieces(deps, env, asset_id)?),
+        QueryMsg::MetadataStatus{ asset_id } => to_binary(&query_metadata_status(deps, env, asset_id)?),
+        QueryMsg::MintPhase{ phase_id } => to_binary(&read_mint_phase(deps.storage, phase_id)?),
+        QueryMsg::MintPhases{ asset_id } => to_binary(&query_mint_phases(deps, env, asset_id)?),
+        QueryMsg::PhaseMints{ phase_id, address }
+            => to_binary(&read_phase_mints(deps.storage, phase_id, &deps.api.addr_validate(&address)?)?),
+        QueryMsg::EndedUnsettled{ start_after, limit } => to_binary(&query_ended_unsettled(deps, env, start_after, limit)?),
+        QueryMsg::EditionAuction{ auction_id } => to_binary(&read_edition_auction(deps.storage, auction_id)?),
+        QueryMsg::Referrer{ address }
+            => to_binary(&read_referrer(deps.storage, &deps.api.addr_validate(&address)?)?),
+        QueryMsg::Offer{ offer_id } => to_binary(&read_offer(deps.storage, offer_id)?),
+        QueryMsg::Offers{ collection, token_id, bidder, start_after, limit }
+            => to_binary(&query_offers(deps, env, collection, token_id, bidder, start_after, limit)?),
+        QueryMsg::CollectionOffer{ offer_id } => to_binary(&read_collection_offer(deps.storage, offer_id)?),
+        QueryMsg::CollectionOffers{ collection, bidder, start_after, limit }
+            => to_binary(&query_collection_offers(deps, env, collection, bidder, start_after, limit)?),
+        QueryMsg::PaymentAssets{}
+            => to_binary(&PaymentAssetsResponse { assets: read_payment_assets(deps.storage)? }),
+        QueryMsg::PriceSource{} => to_binary(&read_price_source(deps.storage)?),
+        QueryMsg::RegistryRoyalties{ nft_contract }
+            => to_binary(&read_registry_royalties(deps.storage, &deps.api.addr_validate(&nft_contract)?)?),
+        QueryMsg::CollectionAllowed{ nft_contract }
+            => to_binary(&is_collection_allowed(deps.storage, &deps.api.addr_validate(&nft_contract)?)?),
+    }
+}
+
+pub fn query_nft_info(
+    deps: Deps, 
+    _env: Env,  
+    token_id: String,
+) -> StdResult<Metadata> {
+    let nft_contract_address = read_nft_address(deps.storage)?;
+
+    query_token_metadata(deps, &nft_contract_address, &token_id)
+}
+
+fn query_token_metadata(deps: Deps, contract_addr: &Addr, token_id: &str) -> StdResult<Metadata> {
+    let nft_info: NftInfoResponse<Metadata> = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&Cw721QueryMsg::NftInfo { token_id: token_id.to_string() })?,
+    }))?;
+
+    Ok(nft_info.extension)
+}
+
+pub fn query_all_nft_ids(
+    deps: Deps, 
+    _env: Env,  
+) -> StdResult<Vec<String>> {
+    let nft_contract_address = read_nft_address(deps.storage)?;
+
+    let token_ids: TokensResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: nft_contract_address.to_string(),
         msg: to_binary(&Cw721QueryMsg::AllTokens {start_after: None, limit: None})?,
     }))?;
 
     Ok(token_ids.tokens)
 }
 
+pub fn query_editions(deps: Deps, _env: Env, asset_id: String) -> StdResult<EditionsResponse> {
+    let asset = read_nft_asset(deps.storage, &asset_id)?;
+
+    let token_ids = (1..=asset.minted_editions)
+        .map(|edition| asset.edition_token_id(edition))
+        .collect();
+
+    Ok(EditionsResponse {
+        asset_id: asset.asset_id,
+        num_nfts: asset.metadata.num_nfts,
+        minted_editions: asset.minted_editions,
+        token_ids,
+    })
+}
+
+pub fn query_collections(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+) -> StdResult<CollectionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let collections = read_collections(deps.storage, start_after, limit)?;
+
+    Ok(CollectionsResponse { collections })
+}
+
+pub fn query_ended_unsettled(
+    deps: Deps,
+    env: Env,
+    start_after: Option<EndedListing>,
+    limit: Option<u32>,
+) -> StdResult<EndedUnsettledResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // the index is ordered by end block, so paging resumes after the last entry even once it is settled
+    let start = start_after.as_ref().map(|listing| (listing.end_block, listing.listing_id.as_str()));
+
+    let listings = read_ended_listings(deps.storage, env.block.height, start)
+        .map(|item| item.map(|(end_block, listing_id)| EndedListing { listing_id, end_block }))
+        .take(limit)
+        .collect::<StdResult<Vec<EndedListing>>>()?;
+
+    Ok(EndedUnsettledResponse { listings })
+}
+
+pub fn query_redemptions(
+    deps: Deps,
+    _env: Env,
+    holder: Option<String>,
+    asset_id: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RedemptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let holder = holder.map(|holder| deps.api.addr_validate(&holder)).transpose()?;
+
+    let redemptions = read_redemption_ids(deps.storage, holder.as_ref(), asset_id.as_deref(), start_after)
+        .map(|id| read_redemption(deps.storage, id?))
+        .filter(|item| match item {
+            Ok(redemption) => asset_id.as_ref().is_none_or(|id| *id == redemption.asset_id),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<Redemption>>>()?;
+
+    Ok(RedemptionsResponse { redemptions })
+}
+
+pub fn query_offers(
+    deps: Deps,
+    _env: Env,
+    collection: Option<String>,
+    token_id: Option<String>,
+    bidder: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OffersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let collection = collection.map(|collection| deps.api.addr_validate(&collection)).transpose()?;
+    let bidder = bidder.map(|bidder| deps.api.addr_validate(&bidder)).transpose()?;
+
+    let offers = read_offer_ids(deps.storage, collection.as_ref(), token_id.as_deref(), bidder.as_ref(), start_after)
+        .map(|id| read_offer(deps.storage, id?))
+        .filter(|item| match item {
+            Ok(offer) => token_id.as_ref().is_none_or(|id| *id == offer.token_id)
+                && bidder.as_ref().is_none_or(|bidder| *bidder == offer.bidder),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<Offer>>>()?;
+
+    Ok(OffersResponse { offers })
+}
+
+pub fn query_collection_offers(
+    deps: Deps,
+    _env: Env,
+    collection: Option<String>,
+    bidder: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CollectionOffersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let collection = collection.map(|collection| deps.api.addr_validate(&collection)).transpose()?;
+    let bidder = bidder.map(|bidder| deps.api.addr_validate(&bidder)).transpose()?;
+
+    let offers = read_collection_offer_ids(deps.storage, collection.as_ref(), bidder.as_ref(), start_after)
+        .map(|id| read_collection_offer(deps.storage, id?))
+        .filter(|item| match item {
+            Ok(offer) => bidder.as_ref().is_none_or(|bidder| *bidder == offer.bidder),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<CollectionOffer>>>()?;
+
+    Ok(CollectionOffersResponse { offers })
+}
+
+pub fn query_redeemable_pieces(deps: Deps, _env: Env, asset_id: String) -> StdResult<RedeemablePiecesResponse> {
+    let asset = read_nft_asset(deps.storage, &asset_id)?;
+
+    Ok(RedeemablePiecesResponse {
+        asset_id: asset.asset_id,
+        num_real_repr: asset.metadata.num_real_repr,
+        remaining: asset.metadata.num_real_repr.checked_sub(asset.redeemed_pieces)?,
+    })
+}
+
+pub fn query_metadata_status(deps: Deps, _env: Env, asset_id: String) -> StdResult<MetadataStatusResponse> {
+    let asset = read_nft_asset(deps.storage, &asset_id)?;
+
+    Ok(MetadataStatusResponse {
+        asset_id: asset.asset_id,
+        frozen: asset.frozen,
+        royalties_locked: asset.sold,
+    })
+}
+
+pub fn query_mint_phases(
+    deps: Deps,
+    _env: Env,
+    asset_id: Option<String>,
+) -> StdResult<MintPhasesResponse> {
+    let phases = read_mint_phases(deps.storage)?
+        .into_iter()
+        .filter(|phase| asset_id.as_ref().is_none_or(|id| *id == phase.asset_id))
+        .collect();
+
+    Ok(MintPhasesResponse { phases })
+}
+
 pub fn query_minters(deps: Deps, _env: Env) -> StdResult<Vec<String>> {
     read_minters(deps.storage)  
 }
@@ -414,18 +3587,24 @@ fn query_list_resolver(deps: Deps, _env: Env, id: String) -> StdResult<Binary> {
     // Fetch listing from listing_id
     let key = id.as_bytes();
 
-    let resp = match list_resolver_read(deps.storage).may_load(key)? {
-        Some(listing) => Some(listing),
-        None => None,
-    };
-    let unwrapped_resp = resp.unwrap();
+    let unwrapped_resp = list_resolver_read(deps.storage).load(key)?;
+    let first_item = unwrapped_resp.items.first()
+        .ok_or_else(|| StdError::generic_err("Listing has no items, migrate the contract"))?;
     let resolve_listing = ResolveListingResponse {
-        token_id: unwrapped_resp.token_id,
-        contract_addr: unwrapped_resp.contract_addr,
+        token_id: first_item.token_id.clone(),
+        contract_addr: first_item.contract_addr.clone(),
+        items: unwrapped_resp.items,
         seller: unwrapped_resp.seller,
         max_bid: unwrapped_resp.max_bid,
         max_bidder: unwrapped_resp.max_bidder,
         block_limit: unwrapped_resp.block_limit,
+        escrow: unwrapped_resp.escrow,
+        accepted_assets: unwrapped_resp.accepted_assets,
+        max_bid_expires: unwrapped_resp.max_bid_expires,
+        max_bid_referrer: unwrapped_resp.max_bid_referrer,
+        retraction: unwrapped_resp.retraction,
+        payouts: unwrapped_resp.payouts,
+        access: unwrapped_resp.access,
     };
     to_binary(&resolve_listing)
 }
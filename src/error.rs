@@ -1,4 +1,4 @@
-use cosmwasm_std::{StdError, OverflowError};
+use cosmwasm_std::{StdError, OverflowError, Uint128, Decimal};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -32,4 +32,208 @@ pub enum ContractError {
 
     #[error("Asset type mismatch")]
     AssetInfoMismatch {},
+
+    #[error("Batch must contain at least one item")]
+    EmptyBatch {},
+
+    #[error("Batch size exceeds the maximum of {max}")]
+    BatchSizeExceeded { max: u64 },
+
+    #[error("Batch size must be at least 1")]
+    InvalidBatchSize {},
+
+    #[error("Asset can not have more than {max} editions")]
+    EditionLimitExceeded { max: Uint128 },
+
+    #[error("Collection {id} not found")]
+    CollectionNotFound { id: Uint128 },
+
+    #[error("Minting into collection {id} is closed")]
+    CollectionMintingClosed { id: Uint128 },
+
+    #[error("Collection minting must end after it starts")]
+    InvalidMintWindow {},
+
+    #[error("Collection can not have more than {max} tokens")]
+    CollectionSupplyExceeded { max: Uint128 },
+
+    #[error("Protocol fee rate is larger than 1")]
+    InvalidFeeRate {},
+
+    #[error("Minter has not set a voucher signing key")]
+    MinterKeyNotSet {},
+
+    #[error("Voucher signature is invalid")]
+    InvalidSignature {},
+
+    #[error("Vouchers mint a single edition")]
+    InvalidVoucherEditions {},
+
+    #[error("Voucher expired")]
+    VoucherExpired {},
+
+    #[error("Voucher nonce already used or revoked")]
+    VoucherNonceUsed {},
+
+    #[error("Mint phase must end after it starts")]
+    InvalidPhaseTime {},
+
+    #[error("Invalid merkle root")]
+    InvalidMerkleRoot {},
+
+    #[error("Mint phase is not active")]
+    PhaseNotActive {},
+
+    #[error("Address is not on the allowlist")]
+    NotAllowlisted {},
+
+    #[error("Mint limit of {limit} per address reached")]
+    PhaseLimitReached { limit: u32 },
+
+    #[error("Metadata is frozen")]
+    MetadataFrozen {},
+
+    #[error("Royalties can not change after the first sale")]
+    RoyaltiesLocked {},
+
+    #[error("Invalid royalty address: {address}")]
+    InvalidRoyaltyAddress { address: String },
+
+    #[error("Duplicate royalty recipient: {address}")]
+    DuplicateRoyaltyRecipient { address: String },
+
+    #[error("No more than {max} royalty recipients are allowed")]
+    TooManyRoyaltyRecipients { max: usize },
+
+    #[error("No more than {max} payout recipients are allowed")]
+    TooManyPayoutRecipients { max: usize },
+
+    #[error("Payout shares must be positive, sum to 1 and go to distinct addresses")]
+    InvalidPayoutShares {},
+
+    #[error("Name must not be empty")]
+    EmptyName {},
+
+    #[error("Name is longer than {max} bytes")]
+    NameTooLong { max: usize },
+
+    #[error("Symbol must be {min} to {max} letters, digits or dashes")]
+    InvalidSymbol { min: usize, max: usize },
+
+    #[error("Description is longer than {max} bytes")]
+    DescriptionTooLong { max: usize },
+
+    #[error("URI is longer than {max} bytes")]
+    UriTooLong { max: usize },
+
+    #[error("Unsupported URI scheme: {uri}")]
+    InvalidUriScheme { uri: String },
+
+    #[error("num_nfts must be greater than 0")]
+    ZeroSupply {},
+
+    #[error("num_real_repr can not exceed num_nfts")]
+    InvalidRealRepr {},
+
+    #[error("No physical pieces left to redeem")]
+    NoRedeemablePieces {},
+
+    #[error("Redemption is not in the right status for this action")]
+    InvalidRedemptionStatus {},
+
+    #[error("Arbiter is not set")]
+    ArbiterNotSet {},
+
+    #[error("Sale is disputed")]
+    SaleDisputed {},
+
+    #[error("Sale is not disputed")]
+    NotDisputed {},
+
+    #[error("Dispute window is still open")]
+    DisputeWindowOpen {},
+
+    #[error("Dispute window is closed")]
+    DisputeWindowClosed {},
+
+    #[error("Buyer share is larger than 1")]
+    InvalidSplitShare {},
+
+    #[error("Total royalty rate exceeds the maximum of {max}")]
+    RoyaltyCapExceeded { max: Decimal },
+
+    #[error("Offer expired")]
+    OfferExpired {},
+
+    #[error("Not all items of the bundle are deposited")]
+    BundleNotDeposited {},
+
+    #[error("Token is not an item of the bundle or already deposited")]
+    NotABundleItem {},
+
+    #[error("Token {token_id} is in the bundle more than once")]
+    DuplicateBundleItem { token_id: String },
+
+    #[error("Bundle item weights must be positive")]
+    InvalidItemWeight {},
+
+    #[error("Quantity must be positive and divide the escrowed amount")]
+    InvalidBidQuantity {},
+
+    #[error("Bids of an address can not exceed {limit} units")]
+    BidderLimitReached { limit: u64 },
+
+    #[error("Bid expired")]
+    BidExpired {},
+
+    #[error("Bid can not be retracted")]
+    RetractionNotAllowed {},
+
+    #[error("No bid to retract")]
+    NoBidToRetract {},
+
+    #[error("Retraction penalty rate is larger than 1")]
+    InvalidPenaltyRate {},
+
+    #[error("Quantity must be positive and divide the escrowed amount")]
+    InvalidOfferQuantity {},
+
+    #[error("Nft does not have the traits of the offer")]
+    TraitMismatch {},
+
+    #[error("Nft does not match the offer")]
+    OfferMismatch {},
+
+    #[error("No offers left to refund on the token")]
+    NoOffersToRefund {},
+
+    #[error("Asset {asset} is not an accepted payment asset")]
+    UnsupportedPaymentAsset { asset: String },
+
+    #[error("Token has {decimals} decimals")]
+    DecimalsMismatch { decimals: u8 },
+
+    #[error("Asset {asset} is accepted more than once")]
+    DuplicateAsset { asset: String },
+
+    #[error("No price source to compare bids in different assets")]
+    PriceSourceNotSet {},
+
+    #[error("No price for asset {asset}")]
+    AssetNotPriced { asset: String },
+
+    #[error("Payouts exceed the sale amount")]
+    PayoutExceedsSale {},
+
+    #[error("Royalties and protocol fee exceed the voucher price")]
+    VoucherFeesExceedPrice {},
+
+    #[error("{referrer} is not an allowed referrer")]
+    ReferrerNotAllowed { referrer: String },
+
+    #[error("Bidder is not admitted by the access rule of the listing")]
+    BidderNotAllowed {},
+
+    #[error("Collection {collection} can not be listed")]
+    CollectionNotAllowed { collection: String },
 }
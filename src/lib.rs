@@ -1,7 +1,9 @@
 pub mod contract;
 pub mod asset;
 mod error;
+pub mod merkle;
 pub mod msg;
+pub mod payout;
 pub mod state;
 
 #[cfg(test)]
@@ -0,0 +1,31 @@
+use cosmwasm_std::{StdError, StdResult};
+use sha2::{Digest, Sha256};
+
+// Checks a proof for `leaf` against a hex encoded sha256 merkle root.
+// Leaves are sha256 of the raw value and pairs are hashed in sorted order,
+// so proofs do not need to carry left/right positions.
+pub fn verify_proof(root: &str, leaf: &[u8], proof: &[String]) -> StdResult<bool> {
+    let root = decode_hash(root)?;
+    let mut hash: [u8; 32] = Sha256::digest(leaf).into();
+
+    for sibling in proof.iter() {
+        let sibling = decode_hash(sibling)?;
+        let (first, second) = if hash <= sibling { (hash, sibling) } else { (sibling, hash) };
+
+        hash = Sha256::new()
+            .chain(first)
+            .chain(second)
+            .finalize()
+            .into();
+    }
+
+    Ok(hash == root)
+}
+
+pub fn decode_hash(hex_hash: &str) -> StdResult<[u8; 32]> {
+    let mut hash = [0u8; 32];
+    hex::decode_to_slice(hex_hash, &mut hash)
+        .map_err(|_| StdError::generic_err(format!("Invalid sha256 hash: {}", hex_hash)))?;
+
+    Ok(hash)
+}
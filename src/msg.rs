@@ -1,13 +1,19 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Decimal, Uint128};
+use cw721::{Cw721ReceiveMsg, Expiration};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw20::Cw20ReceiveMsg;
-use crate::state::{ Royalty };
-use crate::asset::Asset;
+use crate::state::{ Royalty, Collection, MintPhase, Redemption, PriceSource, PaymentAsset, Offer,
+    CollectionOffer, OfferTraits, RetractionRules,
+    ListingItem, EditionPricing, KeeperReward, PayoutShare, AccessRule };
+use crate::asset::{ Asset, AssetInfo };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {}
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum ExecuteMsg {
     #[serde(rename_all = "snake_case")]
@@ -15,17 +21,100 @@ pub enum ExecuteMsg {
     PlaceListing {
         id: String,
         minimum_bid: Asset,
+        // hold the proceeds until the buyer confirms the delivery
+        escrow: Option<bool>,
+        // cw721 contract of the token, defaults to the registered nft contract
+        nft_contract: Option<String>,
+        // other assets accepted for bids with their minimums
+        accepted_assets: Option<Vec<Asset>>,
+        // allow bids to be retracted under these rules
+        retraction: Option<RetractionRules>,
+        // split the proceeds between these recipients instead of paying the seller
+        payouts: Option<Vec<PayoutShare>>,
+        // only take bids from the bidders admitted by this rule
+        access: Option<AccessRule>,
+    },
+    // Auction several nfts as one lot, which opens once each of them is deposited with DepositBundleItem
+    PlaceBundle {
+        items: Vec<BundleItemMsg>,
+        minimum_bid: Asset,
+        escrow: Option<bool>,
+        accepted_assets: Option<Vec<Asset>>,
+        retraction: Option<RetractionRules>,
+        payouts: Option<Vec<PayoutShare>>,
+        access: Option<AccessRule>,
     },
     // Bid on an NFT already put on Auction
     BidListing {
         listing_id: String,
-        bid_price: Asset
+        bid_price: Asset,
+        // the bid no longer counts after this
+        expires: Option<Expiration>,
+        // allowlisted frontend the bid was placed through
+        referrer: Option<String>,
+        // merkle proof of the bidder address, for listings gated by a merkle root
+        proof: Option<Vec<String>>,
+    },
+    // Escrow a maximum and let the contract bid the minimum needed to lead up to it
+    ProxyBid {
+        listing_id: String,
+        max_bid: Asset,
+        referrer: Option<String>,
+        proof: Option<Vec<String>>,
+    },
+    // Take back the leading bid and outbid bids under the rules of the listing
+    RetractBid {
+        listing_id: String,
+    },
+    // Escrow an offer on any token of a collection, listed or not
+    MakeOffer {
+        collection: String,
+        token_id: String,
+        price: Asset,
+        expires: Expiration,
+        referrer: Option<String>,
+    },
+    // Withdraw an offer, by the bidder or by anybody once it expired
+    CancelOffer {
+        offer_id: u64,
+    },
+    // Refund the offers still open on a token sold to another offer
+    RefundOffers {
+        collection: String,
+        token_id: String,
+    },
+    // Escrow price * quantity for any tokens of a collection with the given traits
+    MakeCollectionOffer {
+        collection: String,
+        price: Asset,
+        quantity: u64,
+        traits: Option<OfferTraits>,
+        expires: Expiration,
+        referrer: Option<String>,
+    },
+    // Withdraw the unfilled part of a collection offer, by the bidder or by anybody once it expired
+    CancelCollectionOffer {
+        offer_id: u64,
     },
     // Withdraw an ended Auction
     WithdrawListing {
         listing_id: String,
     },
+    // Settle several ended auctions, unknown, running or incomplete ones are skipped
+    BatchSettle {
+        listing_ids: Vec<String>,
+    },
     Mint(GFMintMsg),
+    // mint several NFTs with sequential token ids in one message
+    BatchMint {
+        mints: Vec<GFMintMsg>,
+    },
+    // mint further editions of an asset created with num_nfts > 1
+    MintEditions {
+        asset_id: String,
+        amount: u64,
+        owner: String,
+    },
     // register the whitelisted minter or update the expiration time
     UpdateMinter {
         minter: String,
@@ -34,24 +123,293 @@ pub enum ExecuteMsg {
     RemoveMinter {
         minter: String,
     },
-    // receive cw20 token
+    // receive cw20 tokens sent with cw20 Send, only to bid or buy
+    #[serde(rename = "receive")]
     ReceiveToken(Cw20ReceiveMsg),
+    // receive an nft sent with cw721 SendNft
+    #[serde(rename = "receive_nft")]
+    ReceiveNft(Cw721ReceiveMsg),
+    // the issuer shipped the physical piece of a redemption
+    ConfirmShipment {
+        redemption_id: u64,
+        tracking: String,
+    },
+    // the holder received the physical piece, burns the escrowed nft
+    ConfirmReceipt {
+        redemption_id: u64,
+    },
+    // return the escrowed nft of a redemption that has not been shipped yet
+    CancelRedemption {
+        redemption_id: u64,
+    },
     // set nft contract address
     SetNftAddress {
         nft_address: String
-    }
+    },
+    // create a collection owned by the sending minter
+    CreateCollection {
+        name: String,
+        description: Option<String>,
+        default_royalties: Vec<Royalty>,
+        max_supply: Option<Uint128>,
+        // unix time in seconds, minting is open for mint_start <= now < mint_end
+        mint_start: Option<u64>,
+        mint_end: Option<u64>,
+    },
+    // open or close minting into a collection
+    SetCollectionMinting {
+        collection_id: Uint128,
+        open: bool,
+    },
+    // collection creator redirects the royalties owed to them, None pays the creator again
+    SetRoyaltyPayee {
+        collection_id: Uint128,
+        payee: Option<String>,
+    },
+    // owner accepts an asset for listings and bids, cw20 decimals must match the token
+    AddPaymentAsset {
+        info: AssetInfo,
+        decimals: u8,
+        symbol: String,
+    },
+    // owner stops accepting an asset for new listings and bids
+    RemovePaymentAsset {
+        info: AssetInfo,
+    },
+    // owner sets how bids in different assets are compared, None only allows bids in the same asset
+    SetPriceSource {
+        source: Option<PriceSource>,
+    },
+    // owner sets the reward for settling ended listings, None disables it
+    SetKeeperReward {
+        reward: Option<KeeperReward>,
+    },
+    // owner sets the share of the protocol fee paid to the referrer of a sale
+    SetReferralRate {
+        rate: Decimal,
+    },
+    // owner sets the share of the current bid a new bid has to add
+    SetMinBidIncrement {
+        rate: Decimal,
+    },
+    // owner adds and removes referrers from the allowlist
+    UpdateReferrers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    // owner adds and removes the collections that can be listed besides the registered cw721 contract
+    UpdateAllowedCollections {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    // owner sets the royalties of a collection without on-chain royalty info, empty removes them
+    SetRegistryRoyalties {
+        nft_contract: String,
+        royalties: Vec<Royalty>,
+    },
+    // owner sets the maximum number of items handled by one batch message
+    SetMaxBatchSize {
+        size: u64,
+    },
+    // owner sets the share of every sale paid to the treasury
+    SetProtocolFeeRate {
+        rate: Decimal,
+    },
+    // owner sets the address receiving the protocol fee
+    SetTreasury {
+        treasury: String,
+    },
+    // owner sets the seconds after shipping before anybody can complete a redemption
+    SetRedemptionTimeout {
+        timeout: u64,
+    },
+    // owner sets the address settling disputed sales
+    SetArbiter {
+        arbiter: String,
+    },
+    // owner sets the seconds a buyer has to dispute an escrowed sale
+    SetDisputeWindow {
+        window: u64,
+    },
+    // owner sets the highest total royalty rate of a token
+    SetMaxRoyaltyRate {
+        rate: Decimal,
+    },
+    // complete an escrowed sale, by the buyer or by anybody after the dispute window
+    ReleaseEscrow {
+        listing_id: String,
+    },
+    // dispute an escrowed sale, by the buyer or the seller
+    OpenDispute {
+        listing_id: String,
+        reason: String,
+    },
+    // settle a disputed sale, only by the arbiter
+    ResolveDispute {
+        listing_id: String,
+        resolution: DisputeResolution,
+    },
+    // set the secp256k1 public key the sending minter signs vouchers with
+    SetMinterPubkey {
+        pubkey: Binary,
+    },
+    // mint a voucher signed by a minter to the sender, paying the voucher price
+    RedeemVoucher {
+        voucher: MintVoucher,
+        signature: Binary,
+    },
+    // invalidate a voucher nonce of the sending minter
+    RevokeVoucher {
+        nonce: u64,
+    },
+    // update the metadata record of an asset, only by its creator. cw721-base can not change minted
+    // tokens, so the record applies to the editions minted from now on
+    UpdateMetadata {
+        asset_id: String,
+        name: Option<String>,
+        description: Option<String>,
+        external_link: Option<String>,
+        image_uri: Option<String>,
+        royalties: Option<Vec<Royalty>>,
+    },
+    // permanently lock the metadata of an asset
+    FreezeMetadata {
+        asset_id: String,
+    },
+    // open a sale phase for the editions of an asset
+    AddMintPhase {
+        asset_id: String,
+        start_time: u64,
+        end_time: u64,
+        price: Asset,
+        per_address_limit: Option<u32>,
+        merkle_root: Option<String>,
+        treasury_share: Decimal,
+    },
+    // remove a sale phase
+    RemoveMintPhase {
+        phase_id: u64,
+    },
+    // buy an edition from an active phase, proof is required for allowlisted phases
+    MintFromPhase {
+        phase_id: u64,
+        proof: Option<Vec<String>>,
+    },
+    // auction units editions of an asset to the top bids
+    PlaceEditionAuction {
+        asset_id: String,
+        units: u64,
+        minimum_bid: Asset,
+        pricing: EditionPricing,
+        max_per_bidder: Option<u64>,
+        treasury_share: Decimal,
+    },
+    // bid price per unit for quantity editions, escrowing price * quantity
+    BidEditions {
+        auction_id: u64,
+        price: Asset,
+        quantity: u64,
+    },
+    // mint the editions to the winners of an ended edition auction
+    SettleEditionAuction {
+        auction_id: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum Cw20HookMsg {
-    // Place an NFT on Auction
-    PlaceListing {
-        id: String,
-    },
     // Bid on an NFT already put on Auction
     BidListing {
         listing_id: String,
+        expires: Option<Expiration>,
+        referrer: Option<String>,
+        proof: Option<Vec<String>>,
+    },
+    // Proxy bid with the received tokens as maximum
+    ProxyBid {
+        listing_id: String,
+        referrer: Option<String>,
+        proof: Option<Vec<String>>,
+    },
+    // Offer the received tokens for a token
+    MakeOffer {
+        collection: String,
+        token_id: String,
+        expires: Expiration,
+        referrer: Option<String>,
+    },
+    // Offer the received tokens for quantity tokens of a collection, evenly split
+    MakeCollectionOffer {
+        collection: String,
+        quantity: u64,
+        traits: Option<OfferTraits>,
+        expires: Expiration,
+        referrer: Option<String>,
+    },
+    // Redeem a mint voucher paying with cw20 tokens
+    RedeemVoucher {
+        voucher: MintVoucher,
+        signature: Binary,
+    },
+    // Buy an edition from a mint phase paying with cw20 tokens
+    MintFromPhase {
+        phase_id: u64,
+        proof: Option<Vec<String>>,
+    },
+    // Bid the received tokens for quantity editions, evenly split
+    BidEditions {
+        auction_id: u64,
+        quantity: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BundleItemMsg {
+    pub collection: String,
+    pub token_id: String,
+    // royalties are paid on a share of the price proportional to the weight, equal shares by default
+    pub weight: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeResolution {
+    // complete the sale as if the delivery was confirmed
+    ReleaseToSeller {},
+    // refund the buyer and return the nft to the seller
+    RefundBuyer {},
+    // the buyer keeps the nft and gets buyer_share of the price back
+    Split { buyer_share: Decimal },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721HookMsg {
+    // Escrow the nft and request the physical piece it represents
+    RequestRedemption {},
+    // Sell the received nft to an offer on it
+    AcceptOffer {
+        offer_id: u64,
+    },
+    // Sell the received nft to a collection offer
+    FillCollectionOffer {
+        offer_id: u64,
+    },
+    // Escrow the received nft as an item of a bundle listing
+    DepositBundleItem {
+        listing_id: String,
+    },
+    // Put the received nft on auction
+    PlaceListing {
+        minimum_bid: Asset,
+        escrow: Option<bool>,
+        // other assets accepted for bids with their minimums
+        accepted_assets: Option<Vec<Asset>>,
+        retraction: Option<RetractionRules>,
+        payouts: Option<Vec<PayoutShare>>,
+        access: Option<AccessRule>,
     },
 }
 
@@ -69,6 +427,103 @@ pub enum QueryMsg {
     },
     // query all nft ids
     AllTokens{},
+    // query the editions minted so far for an asset
+    Editions {
+        asset_id: String,
+    },
+    // query a collection and its token count
+    Collection {
+        id: Uint128,
+    },
+    // list collections ordered by id
+    Collections {
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+    },
+    // query an escrowed sale waiting for delivery
+    EscrowedSale {
+        listing_id: String,
+    },
+    // query a redemption
+    Redemption {
+        redemption_id: u64,
+    },
+    // list redemptions, optionally of a holder or an asset
+    Redemptions {
+        holder: Option<String>,
+        asset_id: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // query the physical pieces of an asset that can still be redeemed
+    RedeemablePieces {
+        asset_id: String,
+    },
+    // query whether the metadata and royalties of an asset can still change
+    MetadataStatus {
+        asset_id: String,
+    },
+    // query a mint phase
+    MintPhase {
+        phase_id: u64,
+    },
+    // list mint phases, optionally of a single asset
+    MintPhases {
+        asset_id: Option<String>,
+    },
+    // query how many editions an address bought in a phase
+    PhaseMints {
+        phase_id: u64,
+        address: String,
+    },
+    // list ended listings waiting to be settled
+    EndedUnsettled {
+        start_after: Option<EndedListing>,
+        limit: Option<u32>,
+    },
+    // query an edition auction with its winning bids
+    EditionAuction {
+        auction_id: u64,
+    },
+    // query a referrer and its accumulated earnings
+    Referrer {
+        address: String,
+    },
+    // query an offer
+    Offer {
+        offer_id: u64,
+    },
+    // list offers, optionally on a token or of a bidder
+    Offers {
+        collection: Option<String>,
+        token_id: Option<String>,
+        bidder: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // query a collection offer
+    CollectionOffer {
+        offer_id: u64,
+    },
+    // list collection offers, optionally on a collection or of a bidder
+    CollectionOffers {
+        collection: Option<String>,
+        bidder: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // list the assets accepted for listings and bids
+    PaymentAssets {},
+    // query the price source comparing bids in different assets
+    PriceSource {},
+    // query the fallback royalties registered for a collection
+    RegistryRoyalties {
+        nft_contract: String,
+    },
+    // query whether tokens of a collection can be listed
+    CollectionAllowed {
+        nft_contract: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -95,13 +550,68 @@ pub struct GFMintMsg{
     pub init_price: Uint128
 }
 
+// Interface of price oracles usable as price source
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleQueryMsg {
+    // price of one unit of the asset in the quote unit of the oracle
+    Price { asset_info: AssetInfo },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceResponse {
+    pub price: Decimal,
+}
+
+// cw2981 royalty queries, wrapped in the cw721 extension query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw2981QueryMsg {
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    CheckRoyalties {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw2981ExtensionQueryMsg {
+    Extension { msg: Cw2981QueryMsg },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+// Lazy mint payload. The minter signs sha256(contract address || json of the voucher)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintVoucher {
+    pub minter: String,
+    // the owner is replaced by the buyer redeeming the voucher, num_nfts must be 1
+    pub mint: GFMintMsg,
+    pub price: Asset,
+    pub nonce: u64,
+    pub expires: Expiration,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ResolveListingResponse {
+    // the first item, kept for clients of single token listings
     pub token_id: String,
 
     pub contract_addr: Addr,
 
+    pub items: Vec<ListingItem>,
+
     pub seller: Addr,
 
     pub max_bid: Asset,
@@ -109,4 +619,89 @@ pub struct ResolveListingResponse {
     pub max_bidder: Addr,
 
     pub block_limit: u64,
+
+    pub escrow: bool,
+
+    pub accepted_assets: Vec<Asset>,
+
+    pub max_bid_expires: Option<Expiration>,
+
+    pub max_bid_referrer: Option<Addr>,
+
+    pub retraction: Option<RetractionRules>,
+
+    pub payouts: Vec<PayoutShare>,
+
+    pub access: Option<AccessRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EditionsResponse {
+    pub asset_id: String,
+
+    pub num_nfts: Uint128,
+
+    pub minted_editions: u64,
+
+    pub token_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionsResponse {
+    pub collections: Vec<Collection>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EndedUnsettledResponse {
+    pub listings: Vec<EndedListing>,
+}
+
+// an ended listing with the block its auction ended at, also the cursor of the next page
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EndedListing {
+    pub listing_id: String,
+    pub end_block: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPhasesResponse {
+    pub phases: Vec<MintPhase>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MetadataStatusResponse {
+    pub asset_id: String,
+
+    pub frozen: bool,
+
+    pub royalties_locked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OffersResponse {
+    pub offers: Vec<Offer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionOffersResponse {
+    pub offers: Vec<CollectionOffer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentAssetsResponse {
+    pub assets: Vec<PaymentAsset>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionsResponse {
+    pub redemptions: Vec<Redemption>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedeemablePiecesResponse {
+    pub asset_id: String,
+
+    pub num_real_repr: Uint128,
+
+    pub remaining: Uint128,
 }
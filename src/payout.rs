@@ -0,0 +1,174 @@
+use std::convert::TryFrom;
+
+use cosmwasm_std::{Addr, CosmosMsg, Decimal, StdResult, Uint128, Uint256};
+
+use crate::asset::{Asset, AssetInfo};
+use crate::error::ContractError;
+
+// Splits a sale amount between recipients. Shares are rounded down and the
+// rounding dust is left to the seller, so the payouts always sum to the total.
+pub struct PayoutSplit {
+    total: Uint128,
+    payouts: Vec<(Addr, Uint128)>,
+}
+
+impl PayoutSplit {
+    pub fn new(total: Uint128) -> Self {
+        PayoutSplit { total, payouts: vec![] }
+    }
+
+    // payouts to a recipient already in the split are merged
+    pub fn add_amount(&mut self, recipient: Addr, amount: Uint128) -> Result<(), ContractError> {
+        // the allocated amount never exceeds the total, so this can not underflow
+        if amount > self.total - self.allocated() {
+            return Err(ContractError::PayoutExceedsSale {});
+        }
+
+        match self.payouts.iter_mut().find(|(addr, _)| *addr == recipient) {
+            Some((_, total)) => *total += amount,
+            None => self.payouts.push((recipient, amount)),
+        }
+
+        Ok(())
+    }
+
+    pub fn add_share(&mut self, recipient: Addr, rate: Decimal) -> Result<(), ContractError> {
+        let amount = mul_rate(self.total, rate)?;
+        self.add_amount(recipient, amount)
+    }
+
+    pub fn allocated(&self) -> Uint128 {
+        self.payouts.iter().map(|(_, amount)| *amount).sum()
+    }
+
+    // the remainder goes to the seller, zero payouts are dropped
+    pub fn finish(self, seller: Addr) -> Result<Vec<(Addr, Uint128)>, ContractError> {
+        self.finish_shared(&[(seller, Decimal::one())])
+    }
+
+    // the remainder is split by shares summing to 1, the rounding dust goes to the first recipient
+    pub fn finish_shared(mut self, recipients: &[(Addr, Decimal)]) -> Result<Vec<(Addr, Uint128)>, ContractError> {
+        let remainder = self.total.checked_sub(self.allocated())?;
+        let amounts = recipients.iter()
+            .map(|(_, share)| mul_rate(remainder, *share))
+            .collect::<Result<Vec<Uint128>, ContractError>>()?;
+        let shared = amounts.iter().try_fold(Uint128::zero(), |sum, amount| sum.checked_add(*amount))?;
+
+        // shares summing to more than 1 would pay out more than the remainder
+        let dust = remainder.checked_sub(shared).map_err(|_| ContractError::PayoutExceedsSale {})?;
+
+        for (index, ((recipient, _), amount)) in recipients.iter().zip(amounts).enumerate() {
+            let amount = if index == 0 { amount.checked_add(dust)? } else { amount };
+            self.add_amount(recipient.clone(), amount)?;
+        }
+
+        Ok(self.payouts.into_iter().filter(|(_, amount)| !amount.is_zero()).collect())
+    }
+}
+
+// amount * rate rounded down, rates above 1 can exceed what a Uint128 holds
+fn mul_rate(amount: Uint128, rate: Decimal) -> Result<Uint128, ContractError> {
+    let product = amount.full_mul(rate.atomics()) / Uint256::from(Decimal::one().atomics());
+    Uint128::try_from(product).map_err(|_| ContractError::PayoutExceedsSale {})
+}
+
+pub fn payout_msgs(info: &AssetInfo, payouts: Vec<(Addr, Uint128)>) -> StdResult<Vec<CosmosMsg>> {
+    payouts.into_iter()
+        .map(|(recipient, amount)| Asset { info: info.clone(), amount }.into_msg(recipient))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounding_and_merging() {
+        let artist = Addr::unchecked("artist");
+        let seller = Addr::unchecked("seller");
+
+        // 3% of 33 rounds down to 0 and is skipped, 10% twice to the artist is merged,
+        // the dust of 6.6 stays with the seller
+        let mut split = PayoutSplit::new(Uint128::from(33u128));
+        split.add_share(Addr::unchecked("treasury"), Decimal::percent(3)).unwrap();
+        split.add_share(artist.clone(), Decimal::percent(10)).unwrap();
+        split.add_share(artist.clone(), Decimal::percent(10)).unwrap();
+        assert_eq!(split.finish(seller.clone()).unwrap(), vec![
+            (artist.clone(), Uint128::from(6u128)),
+            (seller.clone(), Uint128::from(27u128)),
+        ]);
+
+        // the seller can also be a royalty recipient
+        let mut split = PayoutSplit::new(Uint128::from(100u128));
+        split.add_share(seller.clone(), Decimal::percent(5)).unwrap();
+        split.add_amount(artist.clone(), Uint128::from(20u128)).unwrap();
+        assert_eq!(split.finish(seller.clone()).unwrap(), vec![
+            (seller.clone(), Uint128::from(80u128)),
+            (artist, Uint128::from(20u128)),
+        ]);
+
+        // nothing is sent for an empty sale
+        assert_eq!(PayoutSplit::new(Uint128::zero()).finish(seller).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn exact_sum() {
+        // the shares divide the remainder without dust
+        let mut split = PayoutSplit::new(Uint128::from(100u128));
+        split.add_amount(Addr::unchecked("treasury"), Uint128::from(20u128)).unwrap();
+        let payouts = split.finish_shared(&[
+            (Addr::unchecked("seller"), Decimal::percent(75)),
+            (Addr::unchecked("gallery"), Decimal::percent(25)),
+        ]).unwrap();
+        assert_eq!(payouts, vec![
+            (Addr::unchecked("treasury"), Uint128::from(20u128)),
+            (Addr::unchecked("seller"), Uint128::from(60u128)),
+            (Addr::unchecked("gallery"), Uint128::from(20u128)),
+        ]);
+        assert_eq!(payouts.iter().map(|(_, amount)| *amount).sum::<Uint128>(), Uint128::from(100u128));
+    }
+
+    #[test]
+    fn remainder_to_recipients() {
+        let artist = Addr::unchecked("artist");
+        let seller = Addr::unchecked("seller");
+
+        // the 85 left after royalties are shared, the dust goes to the first recipient
+        let mut split = PayoutSplit::new(Uint128::from(100u128));
+        split.add_share(artist.clone(), Decimal::percent(15)).unwrap();
+        let payouts = split.finish_shared(&[
+            (seller.clone(), Decimal::percent(50)),
+            (Addr::unchecked("gallery"), Decimal::percent(30)),
+            (artist.clone(), Decimal::percent(20)),
+        ]).unwrap();
+        assert_eq!(payouts, vec![
+            (artist, Uint128::from(32u128)),
+            (seller, Uint128::from(43u128)),
+            (Addr::unchecked("gallery"), Uint128::from(25u128)),
+        ]);
+    }
+
+    #[test]
+    fn over_allocation() {
+        let artist = Addr::unchecked("artist");
+        let seller = Addr::unchecked("seller");
+
+        let mut split = PayoutSplit::new(Uint128::from(100u128));
+        split.add_share(artist.clone(), Decimal::percent(60)).unwrap();
+        let err = split.add_amount(seller.clone(), Uint128::from(41u128)).unwrap_err();
+        assert_eq!(err, ContractError::PayoutExceedsSale{});
+
+        // shares above 1 fail instead of panicking, also where the product overflows
+        let err = split.finish_shared(&[
+            (seller.clone(), Decimal::percent(70)),
+            (Addr::unchecked("gallery"), Decimal::percent(40)),
+        ]).unwrap_err();
+        assert_eq!(err, ContractError::PayoutExceedsSale{});
+
+        let mut split = PayoutSplit::new(Uint128::MAX);
+        let err = split.add_share(artist, Decimal::percent(200)).unwrap_err();
+        assert_eq!(err, ContractError::PayoutExceedsSale{});
+        let err = split.finish_shared(&[(seller, Decimal::percent(300))]).unwrap_err();
+        assert_eq!(err, ContractError::PayoutExceedsSale{});
+    }
+}
@@ -1,18 +1,23 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Storage, StdResult, Decimal, Uint128, Order};
+use cosmwasm_std::{Addr, Binary, Storage, StdResult, Decimal, Uint128, Order};
 use cosmwasm_storage::{
     bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton,
     Singleton, prefixed
 };
-use cw_storage_plus::Map;
-use crate::asset::Asset;
+use cw_storage_plus::{Bound, Map};
+use cw721::Expiration;
+use crate::asset::{Asset, AssetInfo};
+use crate::contract::{DEFAULT_MAX_BATCH_SIZE, DEFAULT_REDEMPTION_TIMEOUT, DEFAULT_DISPUTE_WINDOW, DEFAULT_MAX_ROYALTY_PERCENT};
 
 pub static CONFIG_KEY: &[u8] = b"config";
 pub static LIST_RESOLVER_KEY: &[u8] = b"listingresolver";
 pub static CONFIG_MINTER: &[u8] = b"minters";
 pub static CONFIG_NFT: &[u8] = b"nft";
+pub static NFT_ASSET_KEY: &[u8] = b"assets";
+pub static COLLECTION_KEY: &[u8] = b"collections";
+pub static PRICE_SOURCE_KEY: &[u8] = b"price_source";
 
 // pub const OFFERINGS_COUNT: Item<u64> = Item::new(b"num_offerings");
 
@@ -21,6 +26,71 @@ pub struct Config {
     pub listing_count: u64,
     pub owner: String,
     pub max_aution_duration_blocks: u64,
+    // fields below were added after the first release, a config stored before them loads with defaults
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: u64,
+    #[serde(default)]
+    pub collection_count: u64,
+    #[serde(default)]
+    pub phase_count: u64,
+    // share of every sale paid to the treasury
+    #[serde(default)]
+    pub protocol_fee_rate: Decimal,
+    // the owner when empty, set on migration
+    #[serde(default)]
+    pub treasury: String,
+    #[serde(default)]
+    pub redemption_count: u64,
+    // seconds after shipment until a redemption can be completed without the holder
+    #[serde(default = "default_redemption_timeout")]
+    pub redemption_timeout: u64,
+    // resolves disputes of escrowed sales
+    #[serde(default)]
+    pub arbiter: Option<String>,
+    // seconds after settlement during which an escrowed sale can be disputed
+    #[serde(default = "default_dispute_window")]
+    pub dispute_window: u64,
+    // maximum total royalty rate of a token, enforced on mint and settlement
+    #[serde(default = "default_max_royalty_rate")]
+    pub max_royalty_rate: Decimal,
+    #[serde(default)]
+    pub offer_count: u64,
+    #[serde(default)]
+    pub edition_auction_count: u64,
+    // paid out of the protocol fee to whoever settles an ended listing
+    #[serde(default)]
+    pub keeper_reward: Option<KeeperReward>,
+    // share of the protocol fee paid to the referrer of a sale
+    #[serde(default)]
+    pub referral_rate: Decimal,
+    // share of the current bid a new bid has to add, at least one unit
+    #[serde(default)]
+    pub min_bid_increment: Decimal,
+}
+
+fn default_max_batch_size() -> u64 {
+    DEFAULT_MAX_BATCH_SIZE
+}
+
+fn default_redemption_timeout() -> u64 {
+    DEFAULT_REDEMPTION_TIMEOUT
+}
+
+fn default_dispute_window() -> u64 {
+    DEFAULT_DISPUTE_WINDOW
+}
+
+fn default_max_royalty_rate() -> Decimal {
+    Decimal::percent(DEFAULT_MAX_ROYALTY_PERCENT)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeeperReward {
+    // share of the protocol fee
+    FeeShare { rate: Decimal },
+    // amount per asset of the winning bid, none for other assets
+    Fixed { amounts: Vec<Asset> },
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -32,6 +102,65 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, CONFIG_KEY).load()
 }
 
+// Values bids in different assets in a common quote unit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    // fixed price of one unit of each asset
+    Fixed { prices: Vec<AssetPrice> },
+    // contract answering the OracleQueryMsg::Price query
+    Oracle { contract: Addr },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetPrice {
+    pub info: AssetInfo,
+    pub price: Decimal,
+}
+
+pub fn store_price_source(storage: &mut dyn Storage, source: &Option<PriceSource>) -> StdResult<()> {
+    match source {
+        Some(source) => Singleton::new(storage, PRICE_SOURCE_KEY).save(source),
+        None => {
+            Singleton::<PriceSource>::new(storage, PRICE_SOURCE_KEY).remove();
+            Ok(())
+        }
+    }
+}
+
+pub fn read_price_source(storage: &dyn Storage) -> StdResult<Option<PriceSource>> {
+    ReadonlySingleton::new(storage, PRICE_SOURCE_KEY).may_load()
+}
+
+// Asset accepted for listings and bids, with what frontends need to display amounts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PaymentAsset {
+    pub info: AssetInfo,
+    pub decimals: u8,
+    pub symbol: String,
+}
+
+pub const PAYMENT_ASSETS: Map<&str, PaymentAsset> = Map::new("payment_assets");
+
+pub fn store_payment_asset(storage: &mut dyn Storage, asset: &PaymentAsset) -> StdResult<()> {
+    PAYMENT_ASSETS.save(storage, &asset.info.to_string(), asset)
+}
+
+pub fn remove_payment_asset(storage: &mut dyn Storage, info: &AssetInfo) {
+    PAYMENT_ASSETS.remove(storage, &info.to_string())
+}
+
+pub fn read_payment_asset(storage: &dyn Storage, info: &AssetInfo) -> StdResult<Option<PaymentAsset>> {
+    PAYMENT_ASSETS.may_load(storage, &info.to_string())
+}
+
+pub fn read_payment_assets(storage: &dyn Storage) -> StdResult<Vec<PaymentAsset>> {
+    PAYMENT_ASSETS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, asset)| asset))
+        .collect()
+}
+
 pub fn store_minters(storage: &mut dyn Storage, minter: Addr, minter_info: MinterInfo) -> StdResult<()> {
     bucket(storage, CONFIG_MINTER).save(minter.as_bytes(), &minter_info)
 }
@@ -58,17 +187,345 @@ pub fn read_minters(storage: &dyn Storage) -> StdResult<Vec<String>> {
 }
 
 pub fn read_minter_info(storage: &dyn Storage, minter: Addr) -> Option<MinterInfo> {
-    match bucket_read(storage, CONFIG_MINTER).load(minter.as_bytes()){
-        Ok(v) => Some(v),
-        _ => None
+    bucket_read(storage, CONFIG_MINTER).load(minter.as_bytes()).ok()
+}
+
+// nonces of redeemed or revoked vouchers, per minter
+pub const VOUCHER_NONCES: Map<(&Addr, u64), bool> = Map::new("voucher_nonces");
+
+pub fn store_voucher_nonce(storage: &mut dyn Storage, minter: &Addr, nonce: u64) -> StdResult<()> {
+    VOUCHER_NONCES.save(storage, (minter, nonce), &true)
+}
+
+pub fn is_voucher_nonce_used(storage: &dyn Storage, minter: &Addr, nonce: u64) -> StdResult<bool> {
+    Ok(VOUCHER_NONCES.may_load(storage, (minter, nonce))?.is_some())
+}
+
+// A sale window for the editions of an asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPhase {
+    pub id: u64,
+
+    pub asset_id: String,
+
+    // unix time in seconds, the phase is open for start_time <= now < end_time
+    pub start_time: u64,
+
+    pub end_time: u64,
+
+    pub price: Asset,
+
+    pub per_address_limit: Option<u32>,
+
+    // hex encoded root of the allowlist, open to everybody when unset
+    pub merkle_root: Option<String>,
+
+    // share of the proceeds paid to the treasury, the rest goes to the creator
+    pub treasury_share: Decimal,
+
+    // # of editions sold in this phase
+    pub minted: u64,
+}
+
+pub const MINT_PHASES: Map<u64, MintPhase> = Map::new("mint_phases");
+pub const PHASE_MINTS: Map<(u64, &Addr), u32> = Map::new("phase_mints");
+
+pub fn store_mint_phase(storage: &mut dyn Storage, phase: &MintPhase) -> StdResult<()> {
+    MINT_PHASES.save(storage, phase.id, phase)
+}
+
+pub fn read_mint_phase(storage: &dyn Storage, id: u64) -> StdResult<MintPhase> {
+    MINT_PHASES.load(storage, id)
+}
+
+pub fn remove_mint_phase(storage: &mut dyn Storage, id: u64) {
+    MINT_PHASES.remove(storage, id)
+}
+
+pub fn read_mint_phases(storage: &dyn Storage) -> StdResult<Vec<MintPhase>> {
+    MINT_PHASES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, phase)| phase))
+        .collect()
+}
+
+pub fn store_phase_mints(storage: &mut dyn Storage, id: u64, buyer: &Addr, minted: u32) -> StdResult<()> {
+    PHASE_MINTS.save(storage, (id, buyer), &minted)
+}
+
+pub fn read_phase_mints(storage: &dyn Storage, id: u64, buyer: &Addr) -> StdResult<u32> {
+    Ok(PHASE_MINTS.may_load(storage, (id, buyer))?.unwrap_or(0))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EditionPricing {
+    // every winner pays the lowest winning bid
+    Uniform,
+    // every winner pays its own bid
+    Discriminatory,
+}
+
+// A bid on units of an edition auction at a price per unit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EditionBid {
+    pub bidder: Addr,
+    pub price: Uint128,
+    pub quantity: u64,
+}
+
+// An auction of units editions of an asset, won by the top units bids
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EditionAuction {
+    pub id: u64,
+
+    pub asset_id: String,
+
+    pub units: u64,
+
+    // asset of the bids and minimum price per unit
+    pub minimum_bid: Asset,
+
+    pub pricing: EditionPricing,
+
+    pub max_per_bidder: Option<u64>,
+
+    // share of the proceeds paid to the treasury, the rest goes to the creator
+    pub treasury_share: Decimal,
+
+    pub block_limit: u64,
+
+    // winning bids by price, earlier bids first on ties, never more than units in total
+    pub bids: Vec<EditionBid>,
+}
+
+pub const EDITION_AUCTIONS: Map<u64, EditionAuction> = Map::new("edition_auctions");
+
+pub fn store_edition_auction(storage: &mut dyn Storage, auction: &EditionAuction) -> StdResult<()> {
+    EDITION_AUCTIONS.save(storage, auction.id, auction)
+}
+
+pub fn read_edition_auction(storage: &dyn Storage, id: u64) -> StdResult<EditionAuction> {
+    EDITION_AUCTIONS.load(storage, id)
+}
+
+pub fn remove_edition_auction(storage: &mut dyn Storage, id: u64) {
+    EDITION_AUCTIONS.remove(storage, id)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedemptionStatus {
+    Requested,
+    Shipped,
+    Completed,
+    Cancelled,
+}
+
+// Exchange of an escrowed nft for one of the physical pieces it represents
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Redemption {
+    pub id: u64,
+
+    pub token_id: String,
+
+    pub asset_id: String,
+
+    pub holder: Addr,
+
+    // creator of the asset, responsible for shipping the piece
+    pub issuer: Addr,
+
+    pub status: RedemptionStatus,
+
+    pub tracking: Option<String>,
+
+    pub requested_at: u64,
+
+    pub shipped_at: Option<u64>,
+}
+
+pub const REDEMPTIONS: Map<u64, Redemption> = Map::new("redemptions");
+// ids of the redemptions of a holder and of an asset
+pub const REDEMPTIONS_BY_HOLDER: Map<(&Addr, u64), bool> = Map::new("redemptions_by_holder");
+pub const REDEMPTIONS_BY_ASSET: Map<(&str, u64), bool> = Map::new("redemptions_by_asset");
+
+pub fn store_redemption(storage: &mut dyn Storage, redemption: &Redemption) -> StdResult<()> {
+    REDEMPTIONS_BY_HOLDER.save(storage, (&redemption.holder, redemption.id), &true)?;
+    REDEMPTIONS_BY_ASSET.save(storage, (&redemption.asset_id, redemption.id), &true)?;
+    REDEMPTIONS.save(storage, redemption.id, redemption)
+}
+
+pub fn read_redemption(storage: &dyn Storage, id: u64) -> StdResult<Redemption> {
+    REDEMPTIONS.load(storage, id)
+}
+
+// Ids of the redemptions after start_after, walking the index of the filters given
+pub fn read_redemption_ids<'a>(
+    storage: &'a dyn Storage,
+    holder: Option<&Addr>,
+    asset_id: Option<&str>,
+    start_after: Option<u64>,
+) -> Box<dyn Iterator<Item = StdResult<u64>> + 'a> {
+    let start = start_after.map(Bound::exclusive);
+    match (holder, asset_id) {
+        (Some(holder), _) => REDEMPTIONS_BY_HOLDER.prefix(holder).keys(storage, start, None, Order::Ascending),
+        (None, Some(asset_id)) => REDEMPTIONS_BY_ASSET.prefix(asset_id).keys(storage, start, None, Order::Ascending),
+        (None, None) => REDEMPTIONS.keys(storage, start, None, Order::Ascending),
     }
 }
 
+// Escrowed offer on a token that does not need to be listed
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Listing {
+pub struct Offer {
+    pub id: u64,
+
+    pub collection: Addr,
+
     pub token_id: String,
 
-    pub contract_addr: Addr,
+    pub bidder: Addr,
+
+    pub price: Asset,
+
+    pub expires: Expiration,
+
+    pub referrer: Option<Addr>,
+}
+
+pub const OFFERS: Map<u64, Offer> = Map::new("offers");
+// ids of the offers on a token, on a collection and of a bidder
+pub const OFFERS_BY_TOKEN: Map<(&Addr, &str, u64), bool> = Map::new("offers_by_token");
+pub const OFFERS_BY_COLLECTION: Map<(&Addr, u64), bool> = Map::new("offers_by_collection");
+pub const OFFERS_BY_BIDDER: Map<(&Addr, u64), bool> = Map::new("offers_by_bidder");
+
+pub fn store_offer(storage: &mut dyn Storage, offer: &Offer) -> StdResult<()> {
+    OFFERS_BY_TOKEN.save(storage, (&offer.collection, &offer.token_id, offer.id), &true)?;
+    OFFERS_BY_COLLECTION.save(storage, (&offer.collection, offer.id), &true)?;
+    OFFERS_BY_BIDDER.save(storage, (&offer.bidder, offer.id), &true)?;
+    OFFERS.save(storage, offer.id, offer)
+}
+
+pub fn read_offer(storage: &dyn Storage, id: u64) -> StdResult<Offer> {
+    OFFERS.load(storage, id)
+}
+
+pub fn remove_offer(storage: &mut dyn Storage, offer: &Offer) {
+    OFFERS_BY_TOKEN.remove(storage, (&offer.collection, &offer.token_id, offer.id));
+    OFFERS_BY_COLLECTION.remove(storage, (&offer.collection, offer.id));
+    OFFERS_BY_BIDDER.remove(storage, (&offer.bidder, offer.id));
+    OFFERS.remove(storage, offer.id)
+}
+
+// offers placed on a sold token up to this id, still to be refunded
+pub const OFFER_REFUNDS: Map<(&Addr, &str), u64> = Map::new("offer_refunds");
+
+// Ids of the offers on a token up to max_id
+pub fn read_token_offer_ids(
+    storage: &dyn Storage,
+    collection: &Addr,
+    token_id: &str,
+    max_id: u64,
+    limit: usize,
+) -> StdResult<Vec<u64>> {
+    OFFERS_BY_TOKEN
+        .prefix((collection, token_id))
+        .keys(storage, None, Some(Bound::inclusive(max_id)), Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+// Ids of the offers after start_after, walking the most specific index of the filters given
+pub fn read_offer_ids<'a>(
+    storage: &'a dyn Storage,
+    collection: Option<&Addr>,
+    token_id: Option<&str>,
+    bidder: Option<&Addr>,
+    start_after: Option<u64>,
+) -> Box<dyn Iterator<Item = StdResult<u64>> + 'a> {
+    let start = start_after.map(Bound::exclusive);
+    match (collection, token_id, bidder) {
+        (Some(collection), Some(token_id), _)
+            => OFFERS_BY_TOKEN.prefix((collection, token_id)).keys(storage, start, None, Order::Ascending),
+        (Some(collection), None, _)
+            => OFFERS_BY_COLLECTION.prefix(collection).keys(storage, start, None, Order::Ascending),
+        (None, _, Some(bidder)) => OFFERS_BY_BIDDER.prefix(bidder).keys(storage, start, None, Order::Ascending),
+        (None, _, None) => OFFERS.keys(storage, start, None, Order::Ascending),
+    }
+}
+
+// Escrowed offer for a quantity of any tokens of a collection, at a price per token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionOffer {
+    pub id: u64,
+
+    pub collection: Addr,
+
+    pub bidder: Addr,
+
+    pub price: Asset,
+
+    // tokens still wanted, price * quantity is escrowed
+    pub quantity: u64,
+
+    pub traits: Option<OfferTraits>,
+
+    pub expires: Expiration,
+
+    pub referrer: Option<Addr>,
+}
+
+// Metadata the tokens filling a collection offer must have
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OfferTraits {
+    pub collection_id: Option<Uint128>,
+    pub num_real_repr: Option<Uint128>,
+}
+
+pub const COLLECTION_OFFERS: Map<u64, CollectionOffer> = Map::new("collection_offers");
+// ids of the collection offers on a collection and of a bidder
+pub const COLLECTION_OFFERS_BY_COLLECTION: Map<(&Addr, u64), bool> = Map::new("collection_offers_by_collection");
+pub const COLLECTION_OFFERS_BY_BIDDER: Map<(&Addr, u64), bool> = Map::new("collection_offers_by_bidder");
+
+pub fn store_collection_offer(storage: &mut dyn Storage, offer: &CollectionOffer) -> StdResult<()> {
+    COLLECTION_OFFERS_BY_COLLECTION.save(storage, (&offer.collection, offer.id), &true)?;
+    COLLECTION_OFFERS_BY_BIDDER.save(storage, (&offer.bidder, offer.id), &true)?;
+    COLLECTION_OFFERS.save(storage, offer.id, offer)
+}
+
+pub fn read_collection_offer(storage: &dyn Storage, id: u64) -> StdResult<CollectionOffer> {
+    COLLECTION_OFFERS.load(storage, id)
+}
+
+pub fn remove_collection_offer(storage: &mut dyn Storage, offer: &CollectionOffer) {
+    COLLECTION_OFFERS_BY_COLLECTION.remove(storage, (&offer.collection, offer.id));
+    COLLECTION_OFFERS_BY_BIDDER.remove(storage, (&offer.bidder, offer.id));
+    COLLECTION_OFFERS.remove(storage, offer.id)
+}
+
+// Ids of the collection offers after start_after, walking the index of the filters given
+pub fn read_collection_offer_ids<'a>(
+    storage: &'a dyn Storage,
+    collection: Option<&Addr>,
+    bidder: Option<&Addr>,
+    start_after: Option<u64>,
+) -> Box<dyn Iterator<Item = StdResult<u64>> + 'a> {
+    let start = start_after.map(Bound::exclusive);
+    match (collection, bidder) {
+        (Some(collection), _)
+            => COLLECTION_OFFERS_BY_COLLECTION.prefix(collection).keys(storage, start, None, Order::Ascending),
+        (None, Some(bidder))
+            => COLLECTION_OFFERS_BY_BIDDER.prefix(bidder).keys(storage, start, None, Order::Ascending),
+        (None, None) => COLLECTION_OFFERS.keys(storage, start, None, Order::Ascending),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Listing {
+    // tokens sold together, a single one unless the listing is a bundle.
+    // Empty for listings stored before bundles until they are migrated
+    #[serde(default)]
+    pub items: Vec<ListingItem>,
 
     pub seller: Addr,
 
@@ -76,7 +533,206 @@ pub struct Listing {
 
     pub max_bidder: Addr,
 
+    // zero until all items are deposited
     pub block_limit: u64,
+
+    // hold nft and proceeds after the auction until the delivery is confirmed
+    #[serde(default)]
+    pub escrow: bool,
+
+    // assets bids can be made in, each with its own minimum
+    #[serde(default)]
+    pub accepted_assets: Vec<Asset>,
+
+    // escrowed maximum when the max bidder is a proxy, max_bid is what it currently pays
+    #[serde(default)]
+    pub proxy_max: Option<Asset>,
+
+    // after which the max bid is no longer valid
+    #[serde(default)]
+    pub max_bid_expires: Option<Expiration>,
+
+    // frontend the max bid was placed through, paid a share of the fee if it wins
+    #[serde(default)]
+    pub max_bid_referrer: Option<Addr>,
+
+    // outbid bids still escrowed, lowest first, which lead again if the bids above are retracted or expire
+    #[serde(default)]
+    pub prior_bids: Vec<Bid>,
+
+    // when the leading bid may be retracted, never when unset
+    #[serde(default)]
+    pub retraction: Option<RetractionRules>,
+
+    // recipients of the proceeds after royalties and fees, the seller when empty
+    #[serde(default)]
+    pub payouts: Vec<PayoutShare>,
+
+    // who may bid, anybody when unset
+    #[serde(default)]
+    pub access: Option<AccessRule>,
+}
+
+// Single token listing as stored before bundles, read on migration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyListing {
+    pub token_id: String,
+
+    pub contract_addr: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PayoutShare {
+    pub address: String,
+    pub share: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListingItem {
+    pub contract_addr: Addr,
+    pub token_id: String,
+    // share of the price the royalties of this token are paid on, relative to the other items
+    pub weight: u64,
+    pub deposited: bool,
+}
+
+impl ListingItem {
+    // a token held by the contract and sold on its own
+    pub fn new(contract_addr: Addr, token_id: String) -> Self {
+        ListingItem { contract_addr, token_id, weight: 1, deposited: true }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bid {
+    pub bidder: Addr,
+    pub amount: Asset,
+    pub expires: Option<Expiration>,
+    pub referrer: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RetractionRules {
+    // bids can only be retracted more than this many blocks before the end
+    pub min_blocks_before_end: u64,
+    // share of the retracted bid paid to the seller
+    pub penalty_rate: Decimal,
+}
+
+// Bidders admitted to an invite-only listing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessRule {
+    Addresses { addresses: Vec<String> },
+    // bidders prove their address against a hex encoded sha256 merkle root
+    MerkleRoot { root: String },
+    // holders of at least one token of a cw721 collection
+    TokenHolder { collection: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowStatus {
+    Pending,
+    Disputed,
+}
+
+// A won auction of an escrowed listing waiting for the delivery
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowedSale {
+    pub listing_id: String,
+
+    pub items: Vec<ListingItem>,
+
+    pub seller: Addr,
+
+    pub buyer: Addr,
+
+    pub price: Asset,
+
+    // settled the auction, rewarded once the sale is released
+    pub keeper: Addr,
+
+    pub referrer: Option<Addr>,
+
+    pub payouts: Vec<PayoutShare>,
+
+    pub status: EscrowStatus,
+
+    // unix time in seconds until which either party can open a dispute
+    pub dispute_deadline: u64,
+
+    pub dispute_reason: Option<String>,
+}
+
+pub const ESCROWED_SALES: Map<&str, EscrowedSale> = Map::new("escrowed_sales");
+
+// Partner frontend referring bids and offers, its earnings are kept when it leaves the allowlist
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Referrer {
+    pub address: Addr,
+
+    pub allowed: bool,
+
+    // referral fees received so far, one entry per asset
+    pub earnings: Vec<Asset>,
+}
+
+pub const REFERRERS: Map<&Addr, Referrer> = Map::new("referrers");
+
+pub fn store_referrer(storage: &mut dyn Storage, referrer: &Referrer) -> StdResult<()> {
+    REFERRERS.save(storage, &referrer.address, referrer)
+}
+
+// addresses never allowlisted have no earnings
+pub fn read_referrer(storage: &dyn Storage, address: &Addr) -> StdResult<Referrer> {
+    Ok(REFERRERS.may_load(storage, address)?.unwrap_or(Referrer {
+        address: address.clone(),
+        allowed: false,
+        earnings: vec![],
+    }))
+}
+
+pub fn store_escrowed_sale(storage: &mut dyn Storage, sale: &EscrowedSale) -> StdResult<()> {
+    ESCROWED_SALES.save(storage, &sale.listing_id, sale)
+}
+
+pub fn read_escrowed_sale(storage: &dyn Storage, listing_id: &str) -> StdResult<EscrowedSale> {
+    ESCROWED_SALES.load(storage, listing_id)
+}
+
+pub fn remove_escrowed_sale(storage: &mut dyn Storage, listing_id: &str) {
+    ESCROWED_SALES.remove(storage, listing_id)
+}
+
+// royalties paid on sales of collections that expose neither cw2981 nor our metadata
+pub const REGISTRY_ROYALTIES: Map<&Addr, Vec<Royalty>> = Map::new("registry_royalties");
+
+pub fn store_registry_royalties(storage: &mut dyn Storage, nft_contract: &Addr, royalties: &[Royalty]) -> StdResult<()> {
+    if royalties.is_empty() {
+        REGISTRY_ROYALTIES.remove(storage, nft_contract);
+        return Ok(());
+    }
+    REGISTRY_ROYALTIES.save(storage, nft_contract, &royalties.to_vec())
+}
+
+pub fn read_registry_royalties(storage: &dyn Storage, nft_contract: &Addr) -> StdResult<Vec<Royalty>> {
+    Ok(REGISTRY_ROYALTIES.may_load(storage, nft_contract)?.unwrap_or_default())
+}
+
+// collections besides the registered cw721 contract that can be listed
+pub const ALLOWED_COLLECTIONS: Map<&Addr, bool> = Map::new("allowed_collections");
+
+pub fn store_allowed_collection(storage: &mut dyn Storage, nft_contract: &Addr, allowed: bool) -> StdResult<()> {
+    if !allowed {
+        ALLOWED_COLLECTIONS.remove(storage, nft_contract);
+        return Ok(());
+    }
+    ALLOWED_COLLECTIONS.save(storage, nft_contract, &true)
+}
+
+pub fn is_collection_allowed(storage: &dyn Storage, nft_contract: &Addr) -> StdResult<bool> {
+    Ok(read_nft_address(storage)? == *nft_contract || ALLOWED_COLLECTIONS.has(storage, nft_contract))
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -87,6 +743,8 @@ pub struct Royalty {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MinterInfo {
+    // secp256k1 public key used to verify the minter's mint vouchers
+    pub pubkey: Option<Binary>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -109,10 +767,153 @@ pub struct Metadata{
     pub init_price: Uint128
 }
 
-pub fn list_resolver(storage: &mut dyn Storage) -> Bucket<Listing> {
+// Parent record of an asset, shared by every edition minted from it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftAsset {
+    pub asset_id: String,
+
+    pub creator: Addr,
+
+    pub token_uri: Option<String>,
+
+    pub metadata: Metadata,
+
+    pub minted_editions: u64,
+
+    // metadata can no longer be updated
+    pub frozen: bool,
+
+    // an edition has been sold at least once, royalties can no longer be updated
+    pub sold: bool,
+
+    // # of physical pieces redeemed or reserved by pending redemptions
+    pub redeemed_pieces: Uint128,
+
+    // editions held back for running edition auctions
+    pub reserved_editions: u64,
+}
+
+impl NftAsset {
+    // single pieces keep the plain asset id, editions are numbered from 1
+    pub fn edition_token_id(&self, edition: u64) -> String {
+        if self.metadata.num_nfts == Uint128::from(1u128) {
+            self.asset_id.clone()
+        } else {
+            format!("{}#{}", self.asset_id, edition)
+        }
+    }
+}
+
+// asset id of an edition token id, e.g. GF.12 for GF.12#3
+pub fn asset_id_of(token_id: &str) -> &str {
+    token_id.split('#').next().unwrap_or(token_id)
+}
+
+pub fn store_nft_asset(storage: &mut dyn Storage, asset: &NftAsset) -> StdResult<()> {
+    bucket(storage, NFT_ASSET_KEY).save(asset.asset_id.as_bytes(), asset)
+}
+
+pub fn read_nft_asset(storage: &dyn Storage, asset_id: &str) -> StdResult<NftAsset> {
+    bucket_read(storage, NFT_ASSET_KEY).load(asset_id.as_bytes())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Collection {
+    pub id: Uint128,
+
+    pub name: String,
+
+    pub description: Option<String>,
+
+    pub creator: Addr,
+
+    // applied to mints into this collection that do not set their own royalties
+    pub default_royalties: Vec<Royalty>,
+
+    pub max_supply: Option<Uint128>,
+
+    pub minting_open: bool,
+
+    // unix time in seconds, mints are accepted for mint_start <= now < mint_end
+    pub mint_start: Option<u64>,
+
+    pub mint_end: Option<u64>,
+
+    // # of tokens minted into this collection
+    pub num_tokens: Uint128,
+
+    // tokens held back for edition auctions, counted against max_supply
+    pub reserved_tokens: Uint128,
+
+    // receives the royalties owed to the creator instead of the creator
+    pub royalty_payee: Option<Addr>,
+}
+
+impl Collection {
+    // minting is open while the creator keeps it open and within the mint window
+    pub fn is_minting_open(&self, now: u64) -> bool {
+        self.minting_open
+            && self.mint_start.is_none_or(|start| now >= start)
+            && self.mint_end.is_none_or(|end| now < end)
+    }
+}
+
+pub fn store_collection(storage: &mut dyn Storage, collection: &Collection) -> StdResult<()> {
+    bucket(storage, COLLECTION_KEY).save(&collection.id.u128().to_be_bytes(), collection)
+}
+
+pub fn read_collection(storage: &dyn Storage, id: Uint128) -> StdResult<Collection> {
+    bucket_read(storage, COLLECTION_KEY).load(&id.u128().to_be_bytes())
+}
+
+pub fn read_collections(
+    storage: &dyn Storage,
+    start_after: Option<Uint128>,
+    limit: usize,
+) -> StdResult<Vec<Collection>> {
+    // keys are big endian so the exclusive start is the next id, nothing follows the largest one
+    let start = match start_after.map(|id| id.u128().checked_add(1)) {
+        Some(Some(next)) => Some(next.to_be_bytes().to_vec()),
+        Some(None) => return Ok(vec![]),
+        None => None,
+    };
+
+    bucket_read(storage, COLLECTION_KEY)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, collection)| collection))
+        .collect()
+}
+
+pub fn list_resolver(storage: &mut dyn Storage) -> Bucket<'_, Listing> {
     bucket(storage, LIST_RESOLVER_KEY)
 }
 
-pub fn list_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<Listing> {
+pub fn list_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<'_, Listing> {
+    bucket_read(storage, LIST_RESOLVER_KEY)
+}
+
+pub fn legacy_list_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<'_, LegacyListing> {
     bucket_read(storage, LIST_RESOLVER_KEY)
-}
\ No newline at end of file
+}
+
+// ids of the listings with all items deposited, ordered by the block their auction ends at
+pub const LISTINGS_BY_END: Map<(u64, &str), bool> = Map::new("listings_by_end");
+
+pub fn store_listing_end(storage: &mut dyn Storage, listing_id: &str, block_limit: u64) -> StdResult<()> {
+    LISTINGS_BY_END.save(storage, (block_limit, listing_id), &true)
+}
+
+pub fn remove_listing_end(storage: &mut dyn Storage, listing_id: &str, block_limit: u64) {
+    LISTINGS_BY_END.remove(storage, (block_limit, listing_id))
+}
+
+// Ids of the listings ended before height, after the (block_limit, id) of start_after
+pub fn read_ended_listings<'a>(
+    storage: &'a dyn Storage,
+    height: u64,
+    start_after: Option<(u64, &'a str)>,
+) -> impl Iterator<Item = StdResult<(u64, String)>> + 'a {
+    LISTINGS_BY_END
+        .keys(storage, start_after.map(Bound::exclusive), Some(Bound::exclusive((height, ""))), Order::Ascending)
+}
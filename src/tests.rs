@@ -1,13 +1,28 @@
 #[cfg(test)]
+#[allow(clippy::module_inception, clippy::useless_vec, clippy::unnecessary_cast)]
 mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{ Decimal, Uint128, CosmosMsg, WasmMsg, SubMsg, to_binary };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use std::marker::PhantomData;
+    use cosmwasm_std::{ Addr, Decimal, StdError, Uint128, CosmosMsg, WasmMsg, BankMsg, SubMsg, Deps, DepsMut, Binary, Env, OwnedDeps, WasmQuery, QueryRequest, Querier, QuerierResult,
+        SystemResult, ContractResult, Empty, Storage, Timestamp, attr, coins, from_slice, to_binary, to_vec, from_binary };
+    use cosmwasm_storage::to_length_prefixed;
 
-    use crate::contract::{execute, instantiate, query_minters, query_nft_info};
-    use crate::msg::{ ExecuteMsg, InstantiateMsg, GFMintMsg };
-    use crate::state::{ Royalty, Metadata };
+    use crate::contract::{execute, instantiate, migrate, query, query_minters, query_editions, query_collections, query_metadata_status,
+        query_redeemable_pieces, query_redemptions, MAX_NAME_LENGTH, MAX_ROYALTY_RECIPIENTS, DEFAULT_REDEMPTION_TIMEOUT,
+        DEFAULT_EXPIRE_BLOCKS, DEFAULT_DISPUTE_WINDOW, DEFAULT_MAX_BATCH_SIZE, MAX_PRIOR_BIDS};
+    use crate::msg::{ ExecuteMsg, InstantiateMsg, MigrateMsg, GFMintMsg, BundleItemMsg, MintVoucher, Cw721HookMsg, DisputeResolution,
+        Cw2981QueryMsg, Cw2981ExtensionQueryMsg, CheckRoyaltiesResponse, RoyaltiesInfoResponse, Cw20HookMsg,
+        QueryMsg, PaymentAssetsResponse, ResolveListingResponse,
+        OffersResponse, CollectionOffersResponse, EndedUnsettledResponse, EndedListing };
+    use crate::state::{ Royalty, Metadata, RedemptionStatus, PriceSource, AssetPrice, PaymentAsset, OfferTraits, RetractionRules, ListingItem, EditionPricing, KeeperReward, PayoutShare, Referrer, AccessRule, read_nft_asset, store_nft_asset, read_collection,
+        list_resolver, list_resolver_read, store_nft_address, read_config, CONFIG_NFT, CONFIG_KEY, LIST_RESOLVER_KEY };
+    use crate::asset::{ Asset, AssetInfo };
     use crate::error::ContractError;
-    use cw721_base::msg::{ ExecuteMsg as Cw721ExecuteMsg, MintMsg };
+    use cw721::{ Cw721ExecuteMsg::TransferNft, Cw721ReceiveMsg, Expiration, NftInfoResponse, OwnerOfResponse, TokensResponse };
+    use cw20::{ Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse };
+    use k256::ecdsa::{ SigningKey, Signature, signature::DigestSigner };
+    use sha2::{ Digest, Sha256 };
+    use cw721_base::msg::{ ExecuteMsg as Cw721ExecuteMsg, MintMsg, QueryMsg as Cw721QueryMsg };
 
 
     #[test]
@@ -45,6 +60,18 @@ mod tests {
         let minters = query_minters(deps.as_ref(), mock_env()).unwrap();
         assert_eq!(minters, vec![String::from("minter1")]);
 
+        // the collection has to exist before minting into it
+        let info = mock_info(&String::from("minter1"), &[]);
+        let msg = ExecuteMsg::CreateCollection {
+            name: String::from("first_collection"),
+            description: None,
+            default_royalties: vec![],
+            max_supply: None,
+            mint_start: None,
+            mint_end: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
         let mint_msg = GFMintMsg { 
             owner: String::from("minter1"), 
             name: String::from("first_nft"), 
@@ -118,6 +145,2296 @@ mod tests {
     }
 
 
+    fn mock_init_with_minter(mut deps: DepsMut) {
+        let info = mock_info("creator", &[]);
+        instantiate(deps.branch(), mock_env(), info.clone(), InstantiateMsg {}).unwrap();
+
+        let msg = ExecuteMsg::SetNftAddress{ nft_address: String::from("nft_address")};
+        execute(deps.branch(), mock_env(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateMinter{ minter: String::from("minter1") };
+        execute(deps.branch(), mock_env(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::AddPaymentAsset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            decimals: 6,
+            symbol: String::from("UST"),
+        };
+        execute(deps, mock_env(), info, msg).unwrap();
+    }
+
+    fn mock_mint_msg(name: &str, royalty_rate: Decimal) -> GFMintMsg {
+        GFMintMsg {
+            owner: String::from("minter1"),
+            name: String::from(name),
+            image_uri: Some(String::from("https://glassflow")),
+            external_link: None,
+            description: None,
+            collection: None,
+            num_real_repr: Uint128::from(1u128),
+            num_nfts: Uint128::from(1u128),
+            royalties: vec![Royalty {
+                address: String::from("minter1"),
+                royalty_rate
+            }],
+            init_price: Uint128::from(100u128)
+        }
+    }
+
+    #[test]
+    fn batch_mint() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        // only the owner can change the batch limit
+        let info = mock_info("sender", &[]);
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 2 };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        // a zero limit would make every batch message unusable
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 0 };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBatchSize{});
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 2 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("minter1", &[]);
+
+        // batches larger than the limit are rejected
+        let msg = ExecuteMsg::BatchMint{ mints: vec![
+            mock_mint_msg("a", Decimal::percent(10)),
+            mock_mint_msg("b", Decimal::percent(10)),
+            mock_mint_msg("c", Decimal::percent(10)),
+        ]};
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::BatchSizeExceeded{ max: 2 });
+
+        // one invalid royalty set fails the whole batch without consuming ids
+        let msg = ExecuteMsg::BatchMint{ mints: vec![
+            mock_mint_msg("a", Decimal::percent(10)),
+            mock_mint_msg("b", Decimal::percent(110)),
+        ]};
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRoyaltyRate{});
+
+        let msg = ExecuteMsg::BatchMint{ mints: vec![
+            mock_mint_msg("a", Decimal::percent(10)),
+            mock_mint_msg("b", Decimal::percent(20)),
+        ]};
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(2, res.messages.len());
+
+        assert_eq!(minted_token_ids(&res.messages), vec![String::from("GF.1"), String::from("GF.2")]);
+    }
+
+    fn minted_token_ids(messages: &[SubMsg]) -> Vec<String> {
+        messages.iter().map(|m| match &m.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => {
+                match from_binary(msg).unwrap() {
+                    Cw721ExecuteMsg::<Metadata>::Mint(mint) => mint.token_id,
+                    _ => panic!("unexpected cw721 message"),
+                }
+            }
+            _ => panic!("unexpected message"),
+        }).collect()
+    }
+
+    #[test]
+    fn mint_editions() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 2 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the first max_batch_size editions are minted with the asset
+        let mut mint_msg = mock_mint_msg("edition", Decimal::percent(10));
+        mint_msg.num_nfts = Uint128::from(3u128);
+        let info = mock_info("minter1", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap();
+        assert_eq!(minted_token_ids(&res.messages), vec![String::from("GF.1#1"), String::from("GF.1#2")]);
+
+        // no more than num_nfts editions can ever exist
+        let msg = ExecuteMsg::MintEditions{ asset_id: String::from("GF.1"), amount: 2, owner: String::from("buyer") };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::EditionLimitExceeded{ max: Uint128::from(3u128) });
+
+        let msg = ExecuteMsg::MintEditions{ asset_id: String::from("GF.1"), amount: 1, owner: String::from("buyer") };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(minted_token_ids(&res.messages), vec![String::from("GF.1#3")]);
+
+        let editions = query_editions(deps.as_ref(), mock_env(), String::from("GF.1")).unwrap();
+        assert_eq!(editions.minted_editions, 3);
+        assert_eq!(editions.token_ids, vec![
+            String::from("GF.1#1"), String::from("GF.1#2"), String::from("GF.1#3")
+        ]);
+    }
+
+    #[test]
+    fn mint_into_collection() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let info = mock_info("minter1", &[]);
+        let msg = ExecuteMsg::CreateCollection {
+            name: String::from("drop"),
+            description: Some(String::from("first drop")),
+            default_royalties: vec![Royalty {
+                address: String::from("minter1"),
+                royalty_rate: Decimal::percent(5)
+            }],
+            max_supply: Some(Uint128::from(2u128)),
+            mint_start: None,
+            mint_end: None,
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // minting into an unknown collection fails
+        let mut mint_msg = mock_mint_msg("first", Decimal::percent(10));
+        mint_msg.collection = Some(Uint128::from(2u128));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::CollectionNotFound{ id: Uint128::from(2u128) });
+
+        // the collection default royalties are used when the mint has none
+        let mut mint_msg = mock_mint_msg("first", Decimal::percent(10));
+        mint_msg.collection = Some(Uint128::from(1u128));
+        mint_msg.royalties = vec![];
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap();
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                Cw721ExecuteMsg::<Metadata>::Mint(mint) => {
+                    assert_eq!(mint.extension.collection, Some(Uint128::from(1u128)));
+                    assert_eq!(mint.extension.royalties[0].royalty_rate, Decimal::percent(5));
+                }
+                _ => panic!("unexpected cw721 message"),
+            },
+            _ => panic!("unexpected message"),
+        }
+
+        // editions count towards the max supply
+        let mut mint_msg = mock_mint_msg("second", Decimal::percent(10));
+        mint_msg.collection = Some(Uint128::from(1u128));
+        mint_msg.num_nfts = Uint128::from(2u128);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::CollectionSupplyExceeded{ max: Uint128::from(2u128) });
+
+        // closed collections do not accept mints
+        let msg = ExecuteMsg::SetCollectionMinting{ collection_id: Uint128::from(1u128), open: false };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let mut mint_msg = mock_mint_msg("second", Decimal::percent(10));
+        mint_msg.collection = Some(Uint128::from(1u128));
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::CollectionMintingClosed{ id: Uint128::from(1u128) });
+
+        let res = query_collections(deps.as_ref(), mock_env(), None, None).unwrap();
+        assert_eq!(res.collections.len(), 1);
+        assert_eq!(res.collections[0].num_tokens, Uint128::from(1u128));
+        assert!(!res.collections[0].minting_open);
+
+        let res = query_collections(deps.as_ref(), mock_env(), Some(Uint128::new(u128::MAX)), None).unwrap();
+        assert!(res.collections.is_empty());
+
+        // mints are only accepted within the mint window
+        let now = mock_env().block.time.seconds();
+        let collection = |mint_start: u64, mint_end: u64| ExecuteMsg::CreateCollection {
+            name: String::from("timed"),
+            description: None,
+            default_royalties: vec![],
+            max_supply: None,
+            mint_start: Some(mint_start),
+            mint_end: Some(mint_end),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), collection(now + 10, now + 10)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMintWindow{});
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), collection(now + 10, now + 20)).unwrap();
+
+        let mut mint_msg = mock_mint_msg("timed", Decimal::percent(10));
+        mint_msg.collection = Some(Uint128::from(2u128));
+        let mut env = mock_env();
+        for (time, open) in [(now, false), (now + 10, true), (now + 20, false)] {
+            env.block.time = Timestamp::from_seconds(time);
+            let res = execute(deps.as_mut(), env.clone(), mock_info("minter1", &[]), ExecuteMsg::Mint(mint_msg.clone()));
+            assert_eq!(res.is_ok(), open);
+        }
+    }
+
+    #[test]
+    fn redeem_voucher() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetProtocolFeeRate{ rate: Decimal::percent(5) };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetTreasury{ treasury: String::from("treasury") };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+        let pubkey = Binary::from(signing_key.verifying_key().to_bytes().as_slice());
+        let info = mock_info("minter1", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SetMinterPubkey{ pubkey }).unwrap();
+
+        let voucher = MintVoucher {
+            minter: String::from("minter1"),
+            mint: mock_mint_msg("lazy", Decimal::percent(10)),
+            price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(1000u128),
+            },
+            nonce: 1,
+            expires: Expiration::AtHeight(mock_env().block.height + 100),
+        };
+        // sign sha256(contract address || voucher json) as the contract expects
+        let sign = |voucher: &MintVoucher| {
+            let digest = Sha256::new()
+                .chain(mock_env().contract.address.as_bytes())
+                .chain(to_vec(voucher).unwrap());
+            let signature: Signature = signing_key.sign_digest(digest);
+            Binary::from(signature.as_ref())
+        };
+        let signature = sign(&voucher);
+
+        // a tampered voucher does not verify
+        let mut tampered = voucher.clone();
+        tampered.price.amount = Uint128::from(1u128);
+        let info = mock_info("buyer", &coins(1, "uusd"));
+        let msg = ExecuteMsg::RedeemVoucher{ voucher: tampered, signature: signature.clone() };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature{});
+
+        // cw20 priced vouchers are only paid through the Receive hook
+        let mut cw20_priced = voucher.clone();
+        cw20_priced.price.info = AssetInfo::Token{ contract_addr: String::from("stable") };
+        let msg = ExecuteMsg::RedeemVoucher{ voucher: cw20_priced, signature: signature.clone() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")));
+
+        // one price buys one edition
+        let mut editions = voucher.clone();
+        editions.mint.num_nfts = Uint128::from(3u128);
+        let msg = ExecuteMsg::RedeemVoucher{ voucher: editions, signature: signature.clone() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(1000, "uusd")), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVoucherEditions{});
+
+        let info = mock_info("buyer", &coins(1000, "uusd"));
+        let msg = ExecuteMsg::RedeemVoucher{ voucher: voucher.clone(), signature: signature.clone() };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // mint to the buyer, then the creator's royalty merged with the proceeds and the protocol fee
+        assert_eq!(res.messages.len(), 3);
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+                Cw721ExecuteMsg::<Metadata>::Mint(mint) => assert_eq!(mint.owner, String::from("buyer")),
+                _ => panic!("unexpected cw721 message"),
+            },
+            _ => panic!("unexpected message"),
+        }
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("minter1"),
+            amount: coins(950, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("treasury"),
+            amount: coins(50, "uusd"),
+        }));
+
+        // the same voucher can not be redeemed twice
+        let msg = ExecuteMsg::RedeemVoucher{ voucher: voucher.clone(), signature: signature.clone() };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::VoucherNonceUsed{});
+
+        // revoked nonces are rejected as well
+        let mut revoked = voucher.clone();
+        revoked.nonce = 2;
+        let minter_info = mock_info("minter1", &[]);
+        execute(deps.as_mut(), mock_env(), minter_info, ExecuteMsg::RevokeVoucher{ nonce: 2 }).unwrap();
+        let msg = ExecuteMsg::RedeemVoucher{ voucher: revoked, signature };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::VoucherNonceUsed{});
+
+        // royalties and protocol fee can not take more than the price
+        let msg = ExecuteMsg::SetProtocolFeeRate{ rate: Decimal::percent(60) };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let mut overpaid = voucher.clone();
+        overpaid.nonce = 3;
+        overpaid.mint = mock_mint_msg("lazy", Decimal::percent(50));
+        let msg = ExecuteMsg::RedeemVoucher{ signature: sign(&overpaid), voucher: overpaid };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::VoucherFeesExceedPrice{});
+
+        // the royalty of a voucher minted into a collection goes to the collection payee
+        let msg = ExecuteMsg::SetProtocolFeeRate{ rate: Decimal::percent(5) };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::CreateCollection {
+            name: String::from("lazy collection"),
+            description: None,
+            default_royalties: vec![],
+            max_supply: None,
+            mint_start: None,
+            mint_end: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetRoyaltyPayee{ collection_id: Uint128::from(1u128), payee: Some(String::from("studio")) };
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+        let mut collected = voucher;
+        collected.nonce = 4;
+        collected.mint.collection = Some(Uint128::from(1u128));
+        let msg = ExecuteMsg::RedeemVoucher{ signature: sign(&collected), voucher: collected };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("studio"),
+            amount: coins(100, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("treasury"),
+            amount: coins(50, "uusd"),
+        }));
+        assert_eq!(res.messages[3].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("minter1"),
+            amount: coins(850, "uusd"),
+        }));
+    }
+
+    #[test]
+    fn mint_from_phase() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 1 };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetTreasury{ treasury: String::from("treasury") };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateCollection {
+            name: String::from("drops"),
+            description: None,
+            default_royalties: vec![],
+            max_supply: None,
+            mint_start: None,
+            mint_end: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetRoyaltyPayee{ collection_id: Uint128::from(1u128), payee: Some(String::from("studio")) };
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+
+        // the creator keeps the first edition, the other two are sold in the phase
+        let mut mint_msg = mock_mint_msg("drop", Decimal::percent(10));
+        mint_msg.num_nfts = Uint128::from(3u128);
+        mint_msg.collection = Some(Uint128::from(1u128));
+        let info = mock_info("minter1", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Mint(mint_msg)).unwrap();
+
+        // allowlist of "buyer" and "other"
+        let buyer_leaf = Sha256::digest(b"buyer");
+        let other_leaf = Sha256::digest(b"other");
+        let (first, second) = if buyer_leaf <= other_leaf { (buyer_leaf, other_leaf) } else { (other_leaf, buyer_leaf) };
+        let root = hex::encode(Sha256::new().chain(first).chain(second).finalize());
+
+        let now = mock_env().block.time.seconds();
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::AddMintPhase {
+            asset_id: String::from("GF.1"),
+            start_time: now,
+            end_time: now + 3600,
+            price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            per_address_limit: Some(1),
+            merkle_root: Some(root),
+            treasury_share: Decimal::percent(20),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // addresses outside the allowlist can not mint
+        let info = mock_info("stranger", &coins(100, "uusd"));
+        let msg = ExecuteMsg::MintFromPhase{ phase_id: 1, proof: Some(vec![hex::encode(other_leaf)]) };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::NotAllowlisted{});
+
+        let info = mock_info("buyer", &coins(100, "uusd"));
+        let msg = ExecuteMsg::MintFromPhase{ phase_id: 1, proof: Some(vec![hex::encode(other_leaf)]) };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(minted_token_ids(&res.messages[..1]), vec![String::from("GF.1#2")]);
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("treasury"),
+            amount: coins(20, "uusd"),
+        }));
+        // primary proceeds are no royalties, so they skip the collection payee
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("minter1"),
+            amount: coins(80, "uusd"),
+        }));
+
+        // per address limit
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::PhaseLimitReached{ limit: 1 });
+
+        // the phase is closed after its end time
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let info = mock_info("other", &coins(100, "uusd"));
+        let msg = ExecuteMsg::MintFromPhase{ phase_id: 1, proof: Some(vec![hex::encode(buyer_leaf)]) };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::PhaseNotActive{});
+
+        // phases priced in cw20 are only paid through the Receive hook
+        let msg = ExecuteMsg::AddMintPhase {
+            asset_id: String::from("GF.1"),
+            start_time: now,
+            end_time: now + 3600,
+            price: Asset {
+                info: AssetInfo::Token{ contract_addr: String::from("stable") },
+                amount: Uint128::from(100u128),
+            },
+            per_address_limit: None,
+            merkle_root: None,
+            treasury_share: Decimal::percent(20),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::MintFromPhase{ phase_id: 2, proof: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("thief", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")));
+    }
+
+    #[test]
+    fn edition_auction() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let msg = ExecuteMsg::SetTreasury{ treasury: String::from("treasury") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let set_max_batch_size = |size: u64| ExecuteMsg::SetMaxBatchSize{ size };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), set_max_batch_size(1)).unwrap();
+
+        // the creator keeps the first edition, three of the other four are auctioned
+        let mut mint_msg = mock_mint_msg("drop", Decimal::percent(10));
+        mint_msg.num_nfts = Uint128::from(5u128);
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), ExecuteMsg::Mint(mint_msg)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), set_max_batch_size(3)).unwrap();
+
+        let auction = |units: u64| ExecuteMsg::PlaceEditionAuction {
+            asset_id: String::from("GF.1"),
+            units,
+            minimum_bid: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            pricing: EditionPricing::Uniform,
+            max_per_bidder: Some(2),
+            treasury_share: Decimal::percent(20),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), auction(3)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), auction(3)).unwrap();
+
+        // the auctioned units are reserved
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), auction(2)).unwrap_err();
+        assert_eq!(err, ContractError::EditionLimitExceeded{ max: Uint128::from(5u128) });
+
+        let bid = |bidder: &str, price: u128, quantity: u64| {
+            let msg = ExecuteMsg::BidEditions {
+                auction_id: 1,
+                price: Asset {
+                    info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                    amount: Uint128::from(price),
+                },
+                quantity,
+            };
+            (mock_info(bidder, &coins(price * quantity as u128, "uusd")), msg)
+        };
+        let payment = |to: &str, amount: u128| CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(to),
+            amount: coins(amount, "uusd"),
+        });
+
+        let (info, msg) = bid("alice", 150, 2);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let (info, msg) = bid("bob", 120, 1);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // units pushed out of the top three are refunded
+        let (info, msg) = bid("carol", 130, 2);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.iter().map(|msg| msg.msg.clone()).collect::<Vec<CosmosMsg>>(),
+            vec![payment("carol", 130), payment("bob", 120)]);
+
+        // ties go to the earlier bid
+        let (info, msg) = bid("dave", 130, 1);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFundsSend{});
+
+        let (info, msg) = bid("alice", 200, 1);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::BidderLimitReached{ limit: 2 });
+
+        let (info, msg) = bid("erin", 200, 0);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBidQuantity{});
+
+        // cw20 bids are only escrowed through the Receive hook
+        let msg = ExecuteMsg::BidEditions {
+            auction_id: 1,
+            price: Asset { info: AssetInfo::Token{ contract_addr: String::from("stable") }, amount: Uint128::from(500u128) },
+            quantity: 1,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("thief", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")));
+
+        // every winner pays the lowest winning bid
+        let msg = ExecuteMsg::SettleEditionAuction{ auction_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::AuctionNotEnded{});
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(minted_token_ids(&res.messages[..2]), vec![String::from("GF.1#2"), String::from("GF.1#3")]);
+        assert_eq!(res.messages[2].msg, payment("alice", 40));
+        assert_eq!(minted_token_ids(&res.messages[3..4]), vec![String::from("GF.1#4")]);
+        assert_eq!(res.messages[4].msg, payment("treasury", 78));
+        assert_eq!(res.messages[5].msg, payment("minter1", 312));
+
+        let asset = read_nft_asset(deps.as_ref().storage, "GF.1").unwrap();
+        assert_eq!((asset.minted_editions, asset.reserved_editions), (4, 0));
+
+        // auctioned units hold their place in the collection until settlement
+        let msg = ExecuteMsg::CreateCollection {
+            name: String::from("series"),
+            description: None,
+            default_royalties: vec![],
+            max_supply: Some(Uint128::from(3u128)),
+            mint_start: None,
+            mint_end: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), set_max_batch_size(1)).unwrap();
+        let mut mint_msg = mock_mint_msg("series", Decimal::percent(10));
+        mint_msg.num_nfts = Uint128::from(3u128);
+        mint_msg.collection = Some(Uint128::from(1u128));
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), ExecuteMsg::Mint(mint_msg.clone())).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), set_max_batch_size(3)).unwrap();
+
+        let mut msg = auction(2);
+        if let ExecuteMsg::PlaceEditionAuction { asset_id, .. } = &mut msg {
+            *asset_id = String::from("GF.2");
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::CollectionSupplyExceeded{ max: Uint128::from(3u128) });
+
+        let msg = ExecuteMsg::SetCollectionMinting{ collection_id: Uint128::from(1u128), open: false };
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+
+        let (info, mut msg) = bid("alice", 150, 1);
+        if let ExecuteMsg::BidEditions { auction_id, .. } = &mut msg {
+            *auction_id = 2;
+        }
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SettleEditionAuction{ auction_id: 2 };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(minted_token_ids(&res.messages[..1]), vec![String::from("GF.2#2")]);
+
+        let collection = read_collection(deps.as_ref().storage, Uint128::from(1u128)).unwrap();
+        assert_eq!((collection.num_tokens, collection.reserved_tokens), (Uint128::from(2u128), Uint128::zero()));
+    }
+
+    #[test]
+    fn update_and_freeze_metadata() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let mut mint_msg = mock_mint_msg("typo", Decimal::percent(10));
+        mint_msg.num_nfts = Uint128::from(2u128);
+        let info = mock_info("minter1", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap();
+
+        // only the creator may update
+        let msg = ExecuteMsg::UpdateMetadata {
+            asset_id: String::from("GF.1"),
+            name: Some(String::from("fixed")),
+            description: None,
+            external_link: Some(String::from("https://new")),
+            image_uri: None,
+            royalties: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("sender", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        // cw721-base can not change minted tokens, only the record is updated
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.messages.len(), 0);
+
+        // editions minted afterwards are sent to the nft contract with the new metadata
+        let mint_more = ExecuteMsg::MintEditions{ asset_id: String::from("GF.1"), amount: 1, owner: String::from("buyer") };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), mint_more).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+                assert_eq!(contract_addr, "nft_address");
+                match from_binary(msg).unwrap() {
+                    Cw721ExecuteMsg::<Metadata>::Mint(mint) => {
+                        assert_eq!(mint.token_id, String::from("GF.1#2"));
+                        assert_eq!(mint.extension.name, String::from("fixed"));
+                        assert_eq!(mint.extension.external_link, Some(String::from("https://new")));
+                    },
+                    _ => panic!("unexpected cw721 message"),
+                }
+            },
+            _ => panic!("unexpected message"),
+        }
+
+        // royalties are locked after the first sale
+        let mut asset = read_nft_asset(deps.as_ref().storage, "GF.1").unwrap();
+        asset.sold = true;
+        store_nft_asset(deps.as_mut().storage, &asset).unwrap();
+
+        let royalty_msg = ExecuteMsg::UpdateMetadata {
+            asset_id: String::from("GF.1"),
+            name: None,
+            description: None,
+            external_link: None,
+            image_uri: None,
+            royalties: Some(vec![]),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), royalty_msg).unwrap_err();
+        assert_eq!(err, ContractError::RoyaltiesLocked{});
+
+        let msg_freeze = ExecuteMsg::FreezeMetadata{ asset_id: String::from("GF.1") };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg_freeze).unwrap();
+
+        let status = query_metadata_status(deps.as_ref(), mock_env(), String::from("GF.1")).unwrap();
+        assert!(status.frozen);
+        assert!(status.royalties_locked);
+
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::MetadataFrozen{});
+    }
+
+    #[test]
+    fn mint_validation() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+        let info = mock_info("minter1", &[]);
+
+        let mut mint_msg = mock_mint_msg("zero", Decimal::percent(10));
+        mint_msg.num_nfts = Uint128::zero();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::ZeroSupply{});
+
+        let mut mint_msg = mock_mint_msg("pieces", Decimal::percent(10));
+        mint_msg.num_real_repr = Uint128::from(2u128);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRealRepr{});
+
+        let mut mint_msg = mock_mint_msg("scheme", Decimal::percent(10));
+        mint_msg.external_link = Some(String::from("javascript:alert(1)"));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidUriScheme{ uri: String::from("javascript:alert(1)") });
+
+        let mint_msg = mock_mint_msg(&"x".repeat(MAX_NAME_LENGTH + 1), Decimal::percent(10));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::NameTooLong{ max: MAX_NAME_LENGTH });
+
+        let mut mint_msg = mock_mint_msg("duplicate", Decimal::percent(10));
+        mint_msg.royalties.push(Royalty {
+            address: String::from("minter1"),
+            royalty_rate: Decimal::percent(5)
+        });
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateRoyaltyRecipient{ address: String::from("minter1") });
+
+        let mut mint_msg = mock_mint_msg("recipients", Decimal::percent(10));
+        mint_msg.royalties = (0..=MAX_ROYALTY_RECIPIENTS).map(|i| Royalty {
+            address: format!("artist{}", i),
+            royalty_rate: Decimal::percent(1)
+        }).collect();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::TooManyRoyaltyRecipients{ max: MAX_ROYALTY_RECIPIENTS });
+
+        let mut mint_msg = mock_mint_msg("address", Decimal::percent(10));
+        mint_msg.royalties[0].address = String::from("a");
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRoyaltyAddress{ address: String::from("a") });
+    }
+
+    fn mock_receive_nft(sender: &str, token_id: &str, msg: &Cw721HookMsg) -> ExecuteMsg {
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from(sender),
+            token_id: String::from(token_id),
+            msg: to_binary(msg).unwrap(),
+        })
+    }
+
+    #[test]
+    fn redeem_physical_piece() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        let mut mint_msg = mock_mint_msg("sculpture", Decimal::percent(10));
+        mint_msg.owner = String::from("holder");
+        let info = mock_info("minter1", &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Mint(mint_msg)).unwrap();
+
+        // only nfts of the registered contract are accepted
+        let msg = mock_receive_nft("holder", "GF.1", &Cw721HookMsg::RequestRedemption{});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other_nft", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        let nft_info = mock_info("nft_address", &[]);
+        execute(deps.as_mut(), mock_env(), nft_info.clone(), msg.clone()).unwrap();
+
+        // the single piece is reserved by the pending redemption
+        let err = execute(deps.as_mut(), mock_env(), nft_info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NoRedeemablePieces{});
+
+        // cancelling returns the nft and frees the piece
+        let res = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), ExecuteMsg::CancelRedemption{ redemption_id: 1 }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("holder"), token_id: String::from("GF.1") }).unwrap(),
+        }));
+        let pieces = query_redeemable_pieces(deps.as_ref(), mock_env(), String::from("GF.1")).unwrap();
+        assert_eq!(pieces.remaining, Uint128::from(1u128));
+
+        execute(deps.as_mut(), mock_env(), nft_info, msg).unwrap();
+
+        let msg = ExecuteMsg::ConfirmShipment{ redemption_id: 2, tracking: String::from("TRACK-1") };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg).unwrap();
+
+        // shipped redemptions can not be cancelled
+        let err = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), ExecuteMsg::CancelRedemption{ redemption_id: 2 }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRedemptionStatus{});
+
+        // only the holder can confirm before the timeout, anybody after it
+        let msg = ExecuteMsg::ConfirmReceipt{ redemption_id: 2 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter1", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(DEFAULT_REDEMPTION_TIMEOUT);
+        let res = execute(deps.as_mut(), env, mock_info("minter1", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_binary(&Cw721ExecuteMsg::<Metadata>::Burn { token_id: String::from("GF.1") }).unwrap(),
+        }));
+
+        let redemptions = query_redemptions(deps.as_ref(), mock_env(), Some(String::from("holder")), None, None, None).unwrap();
+        assert_eq!(redemptions.redemptions.len(), 2);
+        assert_eq!(redemptions.redemptions[1].status, RedemptionStatus::Completed);
+        assert_eq!(redemptions.redemptions[1].tracking, Some(String::from("TRACK-1")));
+
+        // the asset index pages from start_after, other holders have none
+        let redemptions = query_redemptions(deps.as_ref(), mock_env(), None, Some(String::from("GF.1")), Some(1), None).unwrap();
+        assert_eq!(redemptions.redemptions.iter().map(|redemption| redemption.id).collect::<Vec<u64>>(), vec![2]);
+        let redemptions = query_redemptions(deps.as_ref(), mock_env(), Some(String::from("minter1")), None, None, None).unwrap();
+        assert!(redemptions.redemptions.is_empty());
+    }
+
+    // MockQuerier of this cosmwasm version can not answer contract queries
+    struct WasmMockQuerier {
+        base: MockQuerier,
+        wasm_handler: Box<dyn Fn(&WasmQuery) -> QuerierResult>,
+    }
+
+    impl Querier for WasmMockQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_slice::<QueryRequest<Empty>>(bin_request) {
+                Ok(QueryRequest::Wasm(query)) => (self.wasm_handler)(&query),
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    fn mock_dependencies_with_wasm<F>(handler: F) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier>
+    where
+        F: Fn(&WasmQuery) -> QuerierResult + 'static,
+    {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: WasmMockQuerier {
+                base: MockQuerier::default(),
+                wasm_handler: Box::new(handler),
+            },
+            custom_query_type: PhantomData,
+        }
+    }
+
+    // answers cw721 NftInfo queries with the given royalties, OwnerOf with "seller" and cw20 TokenInfo with 6 decimals
+    fn mock_dependencies_with_royalties(royalties: Vec<Royalty>) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+        let mut extension = mock_mint_msg("listed", Decimal::zero());
+        extension.royalties = royalties;
+        let nft_info = NftInfoResponse {
+            token_uri: None,
+            extension: Metadata {
+                name: extension.name,
+                description: None,
+                external_link: None,
+                collection: None,
+                num_real_repr: extension.num_real_repr,
+                num_nfts: extension.num_nfts,
+                royalties: extension.royalties,
+                init_price: extension.init_price,
+            },
+        };
+
+        mock_dependencies_with_wasm(move |query| match query {
+            WasmQuery::Smart { msg, .. } => match (from_binary(msg), from_binary(msg)) {
+                (Ok(Cw721QueryMsg::NftInfo { .. }), _) => SystemResult::Ok(ContractResult::Ok(to_binary(&nft_info).unwrap())),
+                (Ok(Cw721QueryMsg::OwnerOf { .. }), _) => SystemResult::Ok(ContractResult::Ok(to_binary(&mock_owner_of("seller")).unwrap())),
+                (_, Ok(Cw20QueryMsg::TokenInfo {})) => SystemResult::Ok(ContractResult::Ok(to_binary(&TokenInfoResponse {
+                    name: String::from("stable"),
+                    symbol: String::from("STBL"),
+                    decimals: 6,
+                    total_supply: Uint128::from(1_000_000u128),
+                }).unwrap())),
+                _ => SystemResult::Ok(ContractResult::Err(String::from("unknown query"))),
+            },
+            _ => panic!("unexpected query"),
+        })
+    }
+
+    // places listing "1" for token GF.1 and bids 150uusd on it
+    fn mock_sold_listing(deps: DepsMut, escrow: bool) {
+        let mut deps = deps;
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            escrow: Some(escrow),
+            nft_contract: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        execute(deps.branch(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::BidListing {
+            listing_id: String::from("1"),
+            bid_price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(150u128),
+            },
+            expires: None,
+            referrer: None,
+            proof: None,
+        };
+        execute(deps, mock_env(), mock_info("buyer", &coins(150, "uusd")), msg).unwrap();
+    }
+
+    fn mock_owner_of(owner: &str) -> OwnerOfResponse {
+        OwnerOfResponse { owner: String::from(owner), approvals: vec![] }
+    }
+
+    fn mock_env_after_auction() -> Env {
+        let mut env = mock_env();
+        env.block.height += DEFAULT_EXPIRE_BLOCKS + 1;
+        env
+    }
+
+    #[test]
+    fn escrowed_sale_dispute() {
+        let mut deps = mock_dependencies_with_royalties(vec![Royalty {
+            address: String::from("artist"),
+            royalty_rate: Decimal::percent(10)
+        }]);
+        mock_init_with_minter(deps.as_mut());
+        mock_sold_listing(deps.as_mut(), true);
+
+        // settlement keeps the nft and the proceeds in the contract
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+
+        let msg = ExecuteMsg::ReleaseEscrow{ listing_id: String::from("1") };
+        let err = execute(deps.as_mut(), mock_env_after_auction(), mock_info("seller", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::DisputeWindowOpen{});
+
+        let dispute = ExecuteMsg::OpenDispute{ listing_id: String::from("1"), reason: String::from("damaged") };
+        let err = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), dispute.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env_after_auction(), mock_info("buyer", &[]), dispute).unwrap();
+
+        // disputed sales wait for the arbiter, even after the window
+        let mut env = mock_env_after_auction();
+        env.block.time = env.block.time.plus_seconds(DEFAULT_DISPUTE_WINDOW);
+        let err = execute(deps.as_mut(), env, mock_info("seller", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::SaleDisputed{});
+
+        let resolve = ExecuteMsg::ResolveDispute {
+            listing_id: String::from("1"),
+            resolution: DisputeResolution::Split{ buyer_share: Decimal::percent(40) },
+        };
+        let err = execute(deps.as_mut(), mock_env_after_auction(), mock_info("arbiter", &[]), resolve.clone()).unwrap_err();
+        assert_eq!(err, ContractError::ArbiterNotSet{});
+
+        let msg = ExecuteMsg::SetArbiter{ arbiter: String::from("arbiter") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env_after_auction(), mock_info("buyer", &[]), resolve.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        // the buyer keeps the nft with 40% back, the rest is a sale with royalties
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("arbiter", &[]), resolve).unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("buyer"), token_id: String::from("GF.1") }).unwrap(),
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("buyer"),
+            amount: coins(60, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: coins(9, "uusd"),
+        }));
+        assert_eq!(res.messages[3].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("seller"),
+            amount: coins(81, "uusd"),
+        }));
+    }
+
+    #[test]
+    fn cross_collection_royalties() {
+        // "cw2981_nft" pays 5% through cw2981, "foreign_nft" has an unknown extension
+        let mut deps = mock_dependencies_with_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "cw2981_nft"
+                && from_binary::<Cw2981ExtensionQueryMsg>(msg).is_ok() => {
+                let res = match from_binary(msg).unwrap() {
+                    Cw2981ExtensionQueryMsg::Extension { msg: Cw2981QueryMsg::CheckRoyalties {} }
+                        => to_binary(&CheckRoyaltiesResponse { royalty_payments: true }),
+                    Cw2981ExtensionQueryMsg::Extension { msg: Cw2981QueryMsg::RoyaltyInfo { sale_price, .. } }
+                        => to_binary(&RoyaltiesInfoResponse {
+                            address: String::from("cw2981_creator"),
+                            royalty_amount: sale_price * Decimal::percent(5),
+                        }),
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            },
+            WasmQuery::Smart { msg, .. } => match from_binary(msg) {
+                Ok(Cw721QueryMsg::NftInfo { .. }) => SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&NftInfoResponse { token_uri: None, extension: Empty {} }).unwrap())),
+                Ok(Cw721QueryMsg::OwnerOf { .. }) => SystemResult::Ok(ContractResult::Ok(to_binary(&mock_owner_of("seller")).unwrap())),
+                _ => SystemResult::Ok(ContractResult::Err(String::from("unknown query"))),
+            },
+            _ => panic!("unexpected query"),
+        });
+        mock_init_with_minter(deps.as_mut());
+
+        let registry = ExecuteMsg::SetRegistryRoyalties {
+            nft_contract: String::from("foreign_nft"),
+            royalties: vec![Royalty { address: String::from("foreign_creator"), royalty_rate: Decimal::percent(10) }],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), registry.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), registry).unwrap();
+
+        let msg = ExecuteMsg::UpdateAllowedCollections { add: vec![String::from("cw2981_nft"), String::from("foreign_nft")], remove: vec![] };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        for (listing_id, nft_contract) in [("1", "cw2981_nft"), ("2", "foreign_nft")] {
+            let msg = ExecuteMsg::PlaceListing {
+                id: String::from("token1"),
+                minimum_bid: Asset {
+                    info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                    amount: Uint128::from(100u128),
+                },
+                escrow: None,
+                nft_contract: Some(String::from(nft_contract)),
+                accepted_assets: None,
+                retraction: None,
+                payouts: None,
+                access: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+            let msg = ExecuteMsg::BidListing {
+                listing_id: String::from(listing_id),
+                bid_price: Asset {
+                    info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                    amount: Uint128::from(150u128),
+                },
+                expires: None,
+                referrer: None,
+                proof: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(150, "uusd")), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("cw2981_nft"),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("buyer"), token_id: String::from("token1") }).unwrap(),
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("cw2981_creator"),
+            amount: coins(7, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("seller"),
+            amount: coins(143, "uusd"),
+        }));
+
+        // the foreign extension is not our Metadata, the registered royalties apply
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("2") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("foreign_creator"),
+            amount: coins(15, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("seller"),
+            amount: coins(135, "uusd"),
+        }));
+    }
+
+    #[test]
+    fn royalty_cap_and_payee() {
+        // the nft contract reports royalties above the cap set later on
+        let mut deps = mock_dependencies_with_royalties(vec![
+            Royalty { address: String::from("minter1"), royalty_rate: Decimal::percent(10) },
+            Royalty { address: String::from("artist"), royalty_rate: Decimal::percent(30) },
+        ]);
+        mock_init_with_minter(deps.as_mut());
+
+        let info = mock_info("minter1", &[]);
+        let msg = ExecuteMsg::CreateCollection {
+            name: String::from("drop"),
+            description: None,
+            default_royalties: vec![],
+            max_supply: None,
+            mint_start: None,
+            mint_end: None,
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let mut mint_msg = mock_mint_msg("first", Decimal::percent(10));
+        mint_msg.collection = Some(Uint128::from(1u128));
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg.clone())).unwrap();
+
+        let msg = ExecuteMsg::SetMaxRoyaltyRate{ rate: Decimal::percent(101) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRoyaltyRate{});
+
+        let msg = ExecuteMsg::SetMaxRoyaltyRate{ rate: Decimal::percent(20) };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        mint_msg.royalties[0].royalty_rate = Decimal::percent(25);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Mint(mint_msg)).unwrap_err();
+        assert_eq!(err, ContractError::RoyaltyCapExceeded{ max: Decimal::percent(20) });
+
+        // only the collection creator sets the payee
+        let msg = ExecuteMsg::SetRoyaltyPayee{ collection_id: Uint128::from(1u128), payee: Some(String::from("studio")) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::BidListing {
+            listing_id: String::from("2"),
+            bid_price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(150u128),
+            },
+            expires: None,
+            referrer: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(150, "uusd")), msg).unwrap();
+
+        // 15 + 45 is scaled down to the 30 allowed, the creator's share goes to the payee
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("2") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("studio"),
+            amount: coins(7, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: coins(22, "uusd"),
+        }));
+        assert_eq!(res.messages[3].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("seller"),
+            amount: coins(121, "uusd"),
+        }));
+    }
+
+    #[test]
+    fn multi_asset_listing() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+        let stable = |amount: u128| Asset {
+            info: AssetInfo::Token{ contract_addr: String::from("stable") },
+            amount: Uint128::from(amount),
+        };
+
+        // only registered assets are accepted, cw20 decimals are checked against the token
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: Some(vec![stable(50)]),
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::UnsupportedPaymentAsset{ asset: String::from("stable") });
+
+        let msg = ExecuteMsg::AddPaymentAsset { info: stable(0).info, decimals: 18, symbol: String::from("STBL") };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DecimalsMismatch{ decimals: 6 });
+
+        for (info, symbol) in [(stable(0).info, "STBL"), (AssetInfo::NativeToken{ denom: String::from("uluna") }, "LUNA")] {
+            let msg = ExecuteMsg::AddPaymentAsset { info, decimals: 6, symbol: String::from(symbol) };
+            let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg.clone()).unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized{});
+            execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        }
+
+        // an asset is registered once, its symbol is a short ticker
+        let msg = ExecuteMsg::AddPaymentAsset { info: stable(0).info, decimals: 6, symbol: String::from("STBL") };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateAsset{ asset: String::from("stable") });
+        for symbol in ["", "UT", "STABLE COIN", "VERYLONGSYMBOL"] {
+            let msg = ExecuteMsg::AddPaymentAsset {
+                info: AssetInfo::NativeToken{ denom: String::from("ukrw") },
+                decimals: 6,
+                symbol: String::from(symbol),
+            };
+            let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidSymbol{ min: 3, max: 12 });
+        }
+
+        let res: PaymentAssetsResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PaymentAssets{}).unwrap()).unwrap();
+        assert_eq!(res.assets.len(), 3);
+        assert_eq!(res.assets[0], PaymentAsset { info: stable(0).info, decimals: 6, symbol: String::from("STBL") });
+
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: Some(vec![stable(50), uusd(10)]),
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateAsset{ asset: String::from("uusd") });
+
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: Some(vec![stable(50)]),
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        // only the owner of the token can list it
+        let err = execute(deps.as_mut(), mock_env(), mock_info("thief", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::BidListing {
+            listing_id: String::from("1"),
+            bid_price: Asset { info: AssetInfo::NativeToken{ denom: String::from("uluna") }, amount: Uint128::from(500u128) },
+            expires: None,
+            referrer: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(500, "uluna")), msg).unwrap_err();
+        assert_eq!(err, ContractError::AssetInfoMismatch{});
+
+        // the first bid only has to reach the minimum of its asset
+        let msg = ExecuteMsg::ReceiveToken(Cw20ReceiveMsg {
+            sender: String::from("bidder1"),
+            amount: Uint128::from(60u128),
+            msg: to_binary(&Cw20HookMsg::BidListing{ listing_id: String::from("1"), expires: None, referrer: None, proof: None }).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("stable", &[]), msg).unwrap();
+
+        let bid = |amount: u128| ExecuteMsg::BidListing { listing_id: String::from("1"), bid_price: uusd(amount), expires: None, referrer: None, proof: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(150, "uusd")), bid(150)).unwrap_err();
+        assert_eq!(err, ContractError::PriceSourceNotSet{});
+
+        let msg = ExecuteMsg::SetPriceSource { source: Some(PriceSource::Fixed { prices: vec![
+            AssetPrice { info: uusd(0).info, price: Decimal::one() },
+            AssetPrice { info: stable(0).info, price: Decimal::percent(200) },
+        ]})};
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // 60 stable are worth 120uusd
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(110, "uusd")), bid(110)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFundsSend{});
+
+        // the outbid bid stays escrowed until the auction is settled
+        let res = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(150, "uusd")), bid(150)).unwrap();
+        assert!(res.messages.is_empty());
+
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("winning_bid", "150uusd"));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("seller"),
+            amount: coins(150, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("stable"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: String::from("bidder1"), amount: Uint128::from(60u128) }).unwrap(),
+        }));
+    }
+
+    #[test]
+    fn token_hooks() {
+        let mut deps = mock_dependencies();
+        mock_init_with_minter(deps.as_mut());
+
+        // cw20 Send and cw721 SendNft reach the contract
+        let msg: ExecuteMsg = from_slice(br#"{"receive":{"sender":"buyer","amount":"1","msg":""}}"#).unwrap();
+        assert!(matches!(msg, ExecuteMsg::ReceiveToken(_)));
+
+        // nfts of allowed collections are listed by sending them with a minimum bid
+        let hook = Cw721HookMsg::PlaceListing {
+            minimum_bid: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            escrow: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other_nft", &[]), mock_receive_nft("seller", "token1", &hook)).unwrap_err();
+        assert_eq!(err, ContractError::CollectionNotAllowed{ collection: String::from("other_nft") });
+
+        let msg = ExecuteMsg::UpdateAllowedCollections { add: vec![String::from("other_nft")], remove: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("other_nft", &[]), mock_receive_nft("seller", "token1", &hook)).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        assert_eq!(res.attributes[1], attr("listing_id", "1"));
+
+        let listing: ResolveListingResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::ResolveListing{ id: String::from("1") }).unwrap()).unwrap();
+        assert_eq!(listing.items, vec![ListingItem::new(Addr::unchecked("other_nft"), String::from("token1"))]);
+        assert_eq!(listing.seller, String::from("seller"));
+
+        // only the registered collection can be redeemed
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other_nft", &[]),
+            mock_receive_nft("seller", "token2", &Cw721HookMsg::RequestRedemption{})).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        // tokens sent without a hook are returned
+        let msg = ExecuteMsg::ReceiveToken(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::from(60u128),
+            msg: Binary::default(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), mock_info("stable", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("stable"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: String::from("buyer"), amount: Uint128::from(60u128) }).unwrap(),
+        }));
+
+        // bids from unregistered cw20 contracts are rejected
+        let msg = ExecuteMsg::ReceiveToken(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::from(500u128),
+            msg: to_binary(&Cw20HookMsg::BidListing{ listing_id: String::from("1"), expires: None, referrer: None, proof: None }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("fake_token", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::UnsupportedPaymentAsset{ asset: String::from("fake_token") });
+    }
+
+    #[test]
+    fn proxy_bidding() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        // a cw20 maximum is only escrowed through the Receive hook
+        let msg = ExecuteMsg::ProxyBid {
+            listing_id: String::from("1"),
+            max_bid: Asset { info: AssetInfo::Token{ contract_addr: String::from("stable") }, amount: Uint128::from(1000u128) },
+            referrer: None,
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("thief", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")));
+
+        let proxy_bid = |deps: DepsMut, bidder: &str, max: u128| {
+            let msg = ExecuteMsg::ProxyBid { listing_id: String::from("1"), max_bid: uusd(max), referrer: None, proof: None };
+            execute(deps, mock_env(), mock_info(bidder, &coins(max, "uusd")), msg).unwrap()
+        };
+        let refund = |to: &str, amount: u128| CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(to),
+            amount: coins(amount, "uusd"),
+        });
+        let top_bid = |deps: Deps| -> ResolveListingResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::ResolveListing{ id: String::from("1") }).unwrap()).unwrap()
+        };
+
+        // the first proxy opens at the minimum, its maximum stays private
+        proxy_bid(deps.as_mut(), "alice", 300);
+        let listing = top_bid(deps.as_ref());
+        assert_eq!((listing.max_bidder.as_str(), listing.max_bid.amount), ("alice", Uint128::from(100u128)));
+
+        // lower bids are answered by the proxy and refunded right away
+        let msg = ExecuteMsg::BidListing { listing_id: String::from("1"), bid_price: uusd(150), expires: None, referrer: None, proof: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(150, "uusd")), msg).unwrap();
+        assert_eq!(res.messages[0].msg, refund("bob", 150));
+        assert_eq!(top_bid(deps.as_ref()).max_bid.amount, Uint128::from(151u128));
+
+        let res = proxy_bid(deps.as_mut(), "carol", 250);
+        assert_eq!(res.messages[0].msg, refund("carol", 250));
+        assert_eq!(top_bid(deps.as_ref()).max_bid.amount, Uint128::from(251u128));
+
+        // the earlier proxy wins ties
+        let res = proxy_bid(deps.as_mut(), "dave", 300);
+        assert_eq!(res.messages[0].msg, refund("dave", 300));
+        let listing = top_bid(deps.as_ref());
+        assert_eq!((listing.max_bidder.as_str(), listing.max_bid.amount), ("alice", Uint128::from(300u128)));
+
+        // a higher proxy leads one unit above the outbid maximum, which stays escrowed as fallback
+        let res = proxy_bid(deps.as_mut(), "erin", 400);
+        assert!(res.messages.is_empty());
+        let listing = top_bid(deps.as_ref());
+        assert_eq!((listing.max_bidder.as_str(), listing.max_bid.amount), ("erin", Uint128::from(301u128)));
+
+        let msg = ExecuteMsg::BidListing { listing_id: String::from("1"), bid_price: uusd(350), expires: None, referrer: None, proof: None };
+        execute(deps.as_mut(), mock_env(), mock_info("frank", &coins(350, "uusd")), msg).unwrap();
+
+        // the winner pays its last bid and gets the rest of the escrow back
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(res.messages[1].msg, refund("seller", 351));
+        assert_eq!(res.messages[2].msg, refund("alice", 300));
+        assert_eq!(res.messages[3].msg, refund("erin", 49));
+    }
+
+    #[test]
+    fn bid_retraction() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+        let refund = |to: &str, amount: u128| CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(to),
+            amount: coins(amount, "uusd"),
+        });
+        let at_height = |height: u64| {
+            let mut env = mock_env();
+            env.block.height = height;
+            env
+        };
+        let start = mock_env().block.height;
+        for retraction in [None, Some(RetractionRules { min_blocks_before_end: 100, penalty_rate: Decimal::percent(10) })] {
+            let msg = ExecuteMsg::PlaceListing {
+                id: String::from("GF.1"),
+                minimum_bid: uusd(100),
+                escrow: None,
+                nft_contract: None,
+                accepted_assets: None,
+                retraction,
+                payouts: None,
+                access: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+        }
+
+        let bid = |listing_id: &str, amount: u128, expires: Option<Expiration>| ExecuteMsg::BidListing {
+            listing_id: String::from(listing_id),
+            bid_price: uusd(amount),
+            expires,
+            referrer: None,
+            proof: None,
+        };
+        let retract = |listing_id: &str| ExecuteMsg::RetractBid { listing_id: String::from(listing_id) };
+
+        let msg = bid("2", 150, Some(Expiration::AtHeight(start)));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(150, "uusd")), msg).unwrap_err();
+        assert_eq!(err, ContractError::BidExpired{});
+
+        // the leader can not retract without rules on the listing
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(150, "uusd")), bid("1", 150, None)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), retract("1")).unwrap_err();
+        assert_eq!(err, ContractError::RetractionNotAllowed{});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), retract("1")).unwrap_err();
+        assert_eq!(err, ContractError::NoBidToRetract{});
+
+        let msg = bid("2", 150, Some(Expiration::AtHeight(start + 10)));
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(150, "uusd")), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(200, "uusd")), bid("2", 200, None)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(250, "uusd")), bid("2", 250, None)).unwrap();
+
+        // outbid bids are retracted under the same rules as the leader
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), retract("2")).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].msg, refund("seller", 20));
+        assert_eq!(res.messages[1].msg, refund("bob", 180));
+
+        // the leader pays the penalty to the seller and the previous bid leads again
+        let res = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), retract("2")).unwrap();
+        assert_eq!(res.messages[0].msg, refund("seller", 25));
+        assert_eq!(res.messages[1].msg, refund("carol", 225));
+        let listing: ResolveListingResponse = from_binary(&query(deps.as_ref(), mock_env(),
+            QueryMsg::ResolveListing{ id: String::from("2") }).unwrap()).unwrap();
+        assert_eq!((listing.max_bidder.as_str(), listing.max_bid.amount), ("alice", Uint128::from(150u128)));
+
+        // once expired the bid is refunded and no longer counts
+        let res = execute(deps.as_mut(), at_height(start + 11), mock_info("dave", &coins(120, "uusd")),
+            bid("2", 120, None)).unwrap();
+        assert_eq!(res.messages[0].msg, refund("alice", 150));
+
+        let end = start + DEFAULT_EXPIRE_BLOCKS;
+        let err = execute(deps.as_mut(), at_height(end - 100), mock_info("dave", &[]), retract("2")).unwrap_err();
+        assert_eq!(err, ContractError::RetractionNotAllowed{});
+
+        // neither can a bid that fell back behind a new leader
+        execute(deps.as_mut(), at_height(start + 11), mock_info("erin", &coins(130, "uusd")), bid("2", 130, None)).unwrap();
+        let err = execute(deps.as_mut(), at_height(end - 100), mock_info("dave", &[]), retract("2")).unwrap_err();
+        assert_eq!(err, ContractError::RetractionNotAllowed{});
+
+        // ties never outbid and the owner can require a minimum increment
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(150, "uusd")), bid("1", 150, None)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFundsSend{});
+        let msg = ExecuteMsg::SetMinBidIncrement{ rate: Decimal::percent(10) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(160, "uusd")), bid("1", 160, None)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFundsSend{});
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(165, "uusd")), bid("1", 165, None)).unwrap();
+
+        // only the latest outbid bids stay escrowed, the oldest is refunded
+        let mut amount = 165u128;
+        for i in 0..MAX_PRIOR_BIDS {
+            amount += amount / 10;
+            let bidder = format!("bidder{}", i);
+            let res = execute(deps.as_mut(), mock_env(), mock_info(&bidder, &coins(amount, "uusd")), bid("1", amount, None)).unwrap();
+            let expected = if i + 1 == MAX_PRIOR_BIDS { vec![refund("alice", 150)] } else { vec![] };
+            assert_eq!(res.messages.iter().map(|msg| msg.msg.clone()).collect::<Vec<CosmosMsg>>(), expected);
+        }
+    }
+
+    #[test]
+    fn bundle_listing() {
+        // "nft_a" pays 10% through cw2981, "nft_b" has no royalties
+        let mut deps = mock_dependencies_with_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "nft_a" => {
+                let res = match from_binary(msg).unwrap() {
+                    Cw2981ExtensionQueryMsg::Extension { msg: Cw2981QueryMsg::CheckRoyalties {} }
+                        => to_binary(&CheckRoyaltiesResponse { royalty_payments: true }),
+                    Cw2981ExtensionQueryMsg::Extension { msg: Cw2981QueryMsg::RoyaltyInfo { sale_price, .. } }
+                        => to_binary(&RoyaltiesInfoResponse {
+                            address: String::from("creator_a"),
+                            royalty_amount: sale_price * Decimal::percent(10),
+                        }),
+                };
+                SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+            },
+            WasmQuery::Smart { msg, .. } => match from_binary(msg) {
+                Ok(Cw721QueryMsg::NftInfo { .. }) => SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&NftInfoResponse { token_uri: None, extension: Empty {} }).unwrap())),
+                _ => SystemResult::Ok(ContractResult::Err(String::from("unknown query"))),
+            },
+            _ => panic!("unexpected query"),
+        });
+        mock_init_with_minter(deps.as_mut());
+
+        let item = |collection: &str, token_id: &str, weight: Option<u64>| BundleItemMsg {
+            collection: String::from(collection),
+            token_id: String::from(token_id),
+            weight,
+        };
+        let bundle = |items: Vec<BundleItemMsg>| ExecuteMsg::PlaceBundle {
+            items,
+            minimum_bid: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            escrow: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        let deposit = Cw721HookMsg::DepositBundleItem { listing_id: String::from("1") };
+        let bid = ExecuteMsg::BidListing {
+            listing_id: String::from("1"),
+            bid_price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(400u128),
+            },
+            expires: None,
+            referrer: None,
+            proof: None,
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]),
+            bundle(vec![item("nft_a", "a1", None), item("nft_a", "a1", None)])).unwrap_err();
+        assert_eq!(err, ContractError::DuplicateBundleItem{ token_id: String::from("a1") });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]),
+            bundle(vec![item("nft_a", "a1", Some(0))])).unwrap_err();
+        assert_eq!(err, ContractError::InvalidItemWeight{});
+
+        let msg = ExecuteMsg::UpdateAllowedCollections { add: vec![String::from("nft_a"), String::from("nft_b")], remove: vec![] };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = bundle(vec![item("nft_a", "a1", Some(3)), item("nft_b", "b1", Some(1))]);
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        // the auction opens once every item is deposited by the seller
+        let err = execute(deps.as_mut(), mock_env(), mock_info("nft_a", &[]), mock_receive_nft("other", "a1", &deposit)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("nft_a", &[]), mock_receive_nft("seller", "b1", &deposit)).unwrap_err();
+        assert_eq!(err, ContractError::NotABundleItem{});
+        execute(deps.as_mut(), mock_env(), mock_info("nft_a", &[]), mock_receive_nft("seller", "a1", &deposit)).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(400, "uusd")), bid.clone()).unwrap_err();
+        assert_eq!(err, ContractError::BundleNotDeposited{});
+        let withdraw = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::BundleNotDeposited{});
+        let msg = QueryMsg::EndedUnsettled { start_after: None, limit: None };
+        let res: EndedUnsettledResponse = from_binary(&query(deps.as_ref(), mock_env_after_auction(), msg.clone()).unwrap()).unwrap();
+        assert!(res.listings.is_empty());
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("nft_b", &[]), mock_receive_nft("seller", "b1", &deposit)).unwrap();
+        assert_eq!(res.attributes[2], attr("auction_opened", "1"));
+        execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(400, "uusd")), bid).unwrap();
+        let res: EndedUnsettledResponse = from_binary(&query(deps.as_ref(), mock_env_after_auction(), msg).unwrap()).unwrap();
+        assert_eq!(res.listings, vec![EndedListing { listing_id: String::from("1"), end_block: mock_env().block.height + DEFAULT_EXPIRE_BLOCKS }]);
+
+        // all items go to the winner, royalties are due on 300 for a1 and 100 for b1
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), withdraw).unwrap();
+        let transfer = |collection: &str, token_id: &str| CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from(collection),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("buyer"), token_id: String::from(token_id) }).unwrap(),
+        });
+        let payment = |to: &str, amount: u128| CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(to),
+            amount: coins(amount, "uusd"),
+        });
+        assert_eq!(res.messages.iter().map(|msg| msg.msg.clone()).collect::<Vec<CosmosMsg>>(), vec![
+            transfer("nft_a", "a1"),
+            transfer("nft_b", "b1"),
+            payment("creator_a", 30),
+            payment("seller", 370),
+        ]);
+    }
+
+    #[test]
+    fn keeper_settlement() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        let msg = ExecuteMsg::SetProtocolFeeRate{ rate: Decimal::percent(10) };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetTreasury{ treasury: String::from("treasury") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetKeeperReward { reward: Some(KeeperReward::FeeShare { rate: Decimal::percent(50) }) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+        // listings 1, 2 and 4 get a bid
+        for token_id in ["token1", "token2", "token3", "token4"] {
+            let msg = ExecuteMsg::PlaceListing {
+                id: String::from(token_id),
+                minimum_bid: uusd(100),
+                escrow: None,
+                nft_contract: None,
+                accepted_assets: None,
+                retraction: None,
+                payouts: None,
+                access: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+        }
+        for listing_id in ["1", "2", "4"] {
+            let msg = ExecuteMsg::BidListing { listing_id: String::from(listing_id), bid_price: uusd(200), expires: None, referrer: None, proof: None };
+            execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(200, "uusd")), msg).unwrap();
+        }
+
+        let ended_unsettled = |deps: Deps, env: Env| -> Vec<String> {
+            let msg = QueryMsg::EndedUnsettled { start_after: None, limit: None };
+            let res: EndedUnsettledResponse = from_binary(&query(deps, env, msg).unwrap()).unwrap();
+            res.listings.into_iter().map(|listing| listing.listing_id).collect()
+        };
+        assert!(ended_unsettled(deps.as_ref(), mock_env()).is_empty());
+        assert_eq!(ended_unsettled(deps.as_ref(), mock_env_after_auction()), vec!["1", "2", "3", "4"]);
+
+        // listing 2 can no longer be settled, its escrow is smaller than the bid
+        let mut listing = list_resolver_read(deps.as_ref().storage).load(b"2").unwrap();
+        listing.proxy_max = Some(uusd(1));
+        list_resolver(deps.as_mut().storage).save(b"2", &listing).unwrap();
+
+        // half of the 10% fee goes to the keeper, unknown ids are skipped and failed ones reported
+        let msg = ExecuteMsg::BatchSettle { listing_ids: vec![String::from("1"), String::from("2"), String::from("3"), String::from("9")] };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("keeper", &[]), msg).unwrap();
+        let payment = |to: &str, amount: u128| CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(to),
+            amount: coins(amount, "uusd"),
+        });
+        assert_eq!(res.messages.len(), 5);
+        assert_eq!(res.messages[1].msg, payment("keeper", 10));
+        assert_eq!(res.messages[2].msg, payment("treasury", 10));
+        assert_eq!(res.messages[3].msg, payment("seller", 180));
+        assert_eq!(res.messages[4].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("seller"), token_id: String::from("token3") }).unwrap(),
+        }));
+        assert!(res.attributes.contains(&attr("settle_failed", "2")));
+        assert_eq!(ended_unsettled(deps.as_ref(), mock_env_after_auction()), vec!["2", "4"]);
+
+        // a listing failing late in the settlement is left as it was
+        let listing = list_resolver_read(deps.as_ref().storage).load(b"4").unwrap();
+        deps.storage.remove(&to_length_prefixed(CONFIG_NFT));
+        let msg = ExecuteMsg::BatchSettle { listing_ids: vec![String::from("4")] };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("keeper", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res.attributes.contains(&attr("settle_failed", "4")));
+        assert_eq!(list_resolver_read(deps.as_ref().storage).load(b"4").unwrap(), listing);
+        store_nft_address(deps.as_mut().storage, &Addr::unchecked("nft_address")).unwrap();
+        assert_eq!(ended_unsettled(deps.as_ref(), mock_env_after_auction()), vec!["2", "4"]);
+
+        // a fixed reward never exceeds the fee
+        let msg = ExecuteMsg::SetKeeperReward { reward: Some(KeeperReward::Fixed { amounts: vec![uusd(50)] }) };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("4") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(res.messages[1].msg, payment("keeper", 20));
+        assert_eq!(res.messages[2].msg, payment("seller", 180));
+    }
+
+    #[test]
+    fn ended_unsettled_pages() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        // listing 3 is placed a block earlier, so its auction ends first
+        let end = mock_env().block.height + DEFAULT_EXPIRE_BLOCKS;
+        for (token_id, end_block) in [("token1", end), ("token2", end), ("token3", end - 1), ("token4", end), ("token5", end)] {
+            let mut env = mock_env();
+            env.block.height = end_block - DEFAULT_EXPIRE_BLOCKS;
+            let msg = ExecuteMsg::PlaceListing {
+                id: String::from(token_id),
+                minimum_bid: Asset {
+                    info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                    amount: Uint128::from(100u128),
+                },
+                escrow: None,
+                nft_contract: None,
+                accepted_assets: None,
+                retraction: None,
+                payouts: None,
+                access: None,
+            };
+            execute(deps.as_mut(), env, mock_info("seller", &[]), msg).unwrap();
+        }
+
+        let ended = |listing_id: &str, end_block: u64| EndedListing { listing_id: String::from(listing_id), end_block };
+        let page = |deps: Deps, start_after: Option<EndedListing>| -> Vec<EndedListing> {
+            let msg = QueryMsg::EndedUnsettled { start_after, limit: Some(2) };
+            from_binary::<EndedUnsettledResponse>(&query(deps, mock_env_after_auction(), msg).unwrap()).unwrap().listings
+        };
+
+        // each page resumes after the last entry of the previous one
+        assert_eq!(page(deps.as_ref(), None), vec![ended("3", end - 1), ended("1", end)]);
+        assert_eq!(page(deps.as_ref(), Some(ended("1", end))), vec![ended("2", end), ended("4", end)]);
+        assert_eq!(page(deps.as_ref(), Some(ended("4", end))), vec![ended("5", end)]);
+        assert!(page(deps.as_ref(), Some(ended("5", end))).is_empty());
+
+        // settling the last entry of a page does not send the next page back to the start
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("2") };
+        execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(page(deps.as_ref(), Some(ended("2", end))), vec![ended("4", end), ended("5", end)]);
+    }
+
+    #[test]
+    fn referral_fees() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        let msg = ExecuteMsg::SetProtocolFeeRate{ rate: Decimal::percent(10) };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetTreasury{ treasury: String::from("treasury") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SetReferralRate { rate: Decimal::percent(30) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("frontend", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateReferrers { add: vec![String::from("frontend")], remove: vec![] };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let bid = |amount: u128, referrer: &str| ExecuteMsg::BidListing {
+            listing_id: String::from("1"),
+            bid_price: uusd(amount),
+            expires: None,
+            referrer: Some(String::from(referrer)),
+            proof: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "uusd")), bid(200, "unknown")).unwrap_err();
+        assert_eq!(err, ContractError::ReferrerNotAllowed { referrer: String::from("unknown") });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("frontend", &coins(200, "uusd")), bid(200, "frontend")).unwrap_err();
+        assert_eq!(err, ContractError::ReferrerNotAllowed { referrer: String::from("frontend") });
+
+        // the referrer of the winning bid gets 30% of the 10% fee
+        let msg = ExecuteMsg::BidListing { listing_id: String::from("1"), bid_price: uusd(200), expires: None, referrer: None, proof: None };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(200, "uusd")), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(300, "uusd")), bid(300, "frontend")).unwrap();
+
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("keeper", &[]), msg).unwrap();
+        let payment = |to: &str, amount: u128| CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from(to),
+            amount: coins(amount, "uusd"),
+        });
+        assert_eq!(res.messages.len(), 5);
+        assert_eq!(res.messages[1].msg, payment("frontend", 9));
+        assert_eq!(res.messages[2].msg, payment("treasury", 21));
+        assert_eq!(res.messages[3].msg, payment("seller", 270));
+        assert_eq!(res.messages[4].msg, payment("alice", 200));
+
+        let referrer: Referrer = from_binary(&query(deps.as_ref(), mock_env(),
+            QueryMsg::Referrer { address: String::from("frontend") }).unwrap()).unwrap();
+        assert!(referrer.allowed);
+        assert_eq!(referrer.earnings, vec![uusd(9)]);
+
+        // proxy bids carry their referrer as well
+        let msg = ExecuteMsg::PlaceListing {
+            id: String::from("GF.3"),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: None,
+            access: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+        let msg = ExecuteMsg::ProxyBid { listing_id: String::from("2"), max_bid: uusd(500), referrer: Some(String::from("frontend")), proof: None };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(500, "uusd")), msg).unwrap();
+
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("2") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(res.messages[1].msg, payment("frontend", 3));
+        assert_eq!(res.messages[4].msg, payment("carol", 400));
+
+        // removed referrers can no longer refer offers
+        let msg = ExecuteMsg::UpdateReferrers { add: vec![], remove: vec![String::from("frontend")] };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::MakeOffer {
+            collection: String::from("nft_address"),
+            token_id: String::from("GF.2"),
+            price: uusd(100),
+            expires: Expiration::AtHeight(mock_env().block.height + 100),
+            referrer: Some(String::from("frontend")),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")), msg).unwrap_err();
+        assert_eq!(err, ContractError::ReferrerNotAllowed { referrer: String::from("frontend") });
+    }
+
+    #[test]
+    fn gated_listing() {
+        // only "holder" owns a token of the "members" collection
+        let mut deps = mock_dependencies_with_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => match (from_binary(msg), from_binary(msg)) {
+                (Ok(Cw721QueryMsg::Tokens { owner, .. }), _) => {
+                    let tokens = if owner == "holder" { vec![String::from("member#1")] } else { vec![] };
+                    SystemResult::Ok(ContractResult::Ok(to_binary(&TokensResponse { tokens }).unwrap()))
+                },
+                (Ok(Cw721QueryMsg::OwnerOf { .. }), _) => SystemResult::Ok(ContractResult::Ok(to_binary(&mock_owner_of("seller")).unwrap())),
+                (_, Ok(Cw20QueryMsg::TokenInfo {})) => SystemResult::Ok(ContractResult::Ok(to_binary(&TokenInfoResponse {
+                    name: String::from("stable"),
+                    symbol: String::from("STBL"),
+                    decimals: 6,
+                    total_supply: Uint128::from(1_000_000u128),
+                }).unwrap())),
+                _ => SystemResult::Ok(ContractResult::Err(String::from("unknown query"))),
+            },
+            _ => panic!("unexpected query"),
+        });
+        mock_init_with_minter(deps.as_mut());
+        let stable = AssetInfo::Token{ contract_addr: String::from("stable") };
+        let msg = ExecuteMsg::AddPaymentAsset { info: stable.clone(), decimals: 6, symbol: String::from("STBL") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+        let listing = |token_id: &str, access: AccessRule| ExecuteMsg::PlaceListing {
+            id: String::from(token_id),
+            minimum_bid: uusd(100),
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: Some(vec![Asset { info: stable.clone(), amount: Uint128::from(100u128) }]),
+            retraction: None,
+            payouts: None,
+            access: Some(access),
+        };
+        let bid = |listing_id: &str, proof: Option<Vec<String>>| ExecuteMsg::BidListing {
+            listing_id: String::from(listing_id),
+            bid_price: uusd(150),
+            expires: None,
+            referrer: None,
+            proof,
+        };
+
+        // allowlist of "alice" and "bob"
+        let alice_leaf = Sha256::digest(b"alice");
+        let bob_leaf = Sha256::digest(b"bob");
+        let (first, second) = if alice_leaf <= bob_leaf { (alice_leaf, bob_leaf) } else { (bob_leaf, alice_leaf) };
+        let root = hex::encode(Sha256::new().chain(first).chain(second).finalize());
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]),
+            listing("GF.1", AccessRule::MerkleRoot { root: String::from("root") })).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMerkleRoot{});
+        for (token_id, access) in [
+            ("GF.1", AccessRule::Addresses { addresses: vec![String::from("alice")] }),
+            ("GF.2", AccessRule::MerkleRoot { root }),
+            ("GF.3", AccessRule::TokenHolder { collection: String::from("members") }),
+        ] {
+            execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), listing(token_id, access)).unwrap();
+        }
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(150, "uusd")), bid("1", None)).unwrap_err();
+        assert_eq!(err, ContractError::BidderNotAllowed{});
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(150, "uusd")), bid("1", None)).unwrap();
+
+        let proof = Some(vec![hex::encode(alice_leaf)]);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(150, "uusd")), bid("2", proof.clone())).unwrap_err();
+        assert_eq!(err, ContractError::BidderNotAllowed{});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(150, "uusd")),
+            bid("2", Some(vec![String::from("not hex")]))).unwrap_err();
+        assert_eq!(err, ContractError::BidderNotAllowed{});
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(150, "uusd")), bid("2", proof)).unwrap();
+
+        // cw20 and proxy bids are gated as well
+        let msg = ExecuteMsg::ReceiveToken(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::from(150u128),
+            msg: to_binary(&Cw20HookMsg::BidListing{ listing_id: String::from("3"), expires: None, referrer: None, proof: None }).unwrap(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("stable", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::BidderNotAllowed{});
+        let msg = ExecuteMsg::ProxyBid { listing_id: String::from("3"), max_bid: uusd(300), referrer: None, proof: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(300, "uusd")), msg).unwrap_err();
+        assert_eq!(err, ContractError::BidderNotAllowed{});
+        execute(deps.as_mut(), mock_env(), mock_info("holder", &coins(150, "uusd")), bid("3", None)).unwrap();
+    }
+
+    #[test]
+    fn offers() {
+        let mut deps = mock_dependencies_with_royalties(vec![Royalty {
+            address: String::from("artist"),
+            royalty_rate: Decimal::percent(10)
+        }]);
+        mock_init_with_minter(deps.as_mut());
+
+        let expires = Expiration::AtHeight(mock_env().block.height + 100);
+        let offer = |token_id: &str, amount: u128, expires: Expiration| ExecuteMsg::MakeOffer {
+            collection: String::from("nft_address"),
+            token_id: String::from(token_id),
+            price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(amount),
+            },
+            expires,
+            referrer: None,
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")),
+            offer("GF.1", 100, Expiration::AtHeight(1))).unwrap_err();
+        assert_eq!(err, ContractError::OfferExpired{});
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")), offer("GF.1", 100, expires)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(120, "uusd")), offer("GF.1", 120, expires)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(50, "uusd")), offer("GF.2", 50, expires)).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::CancelOffer{ offer_id: 2 }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        let offers_of = |deps: Deps, token_id: Option<&str>, bidder: Option<&str>| -> OffersResponse {
+            let msg = QueryMsg::Offers {
+                collection: Some(String::from("nft_address")),
+                token_id: token_id.map(String::from),
+                bidder: bidder.map(String::from),
+                start_after: None,
+                limit: None,
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(offers_of(deps.as_ref(), Some("GF.1"), None).offers.len(), 2);
+        assert_eq!(offers_of(deps.as_ref(), None, Some("carol")).offers[0].token_id, String::from("GF.2"));
+
+        // cw20 offers are only escrowed through the Receive hook
+        let stable = AssetInfo::Token{ contract_addr: String::from("stable") };
+        let msg = ExecuteMsg::AddPaymentAsset { info: stable.clone(), decimals: 6, symbol: String::from("STBL") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::MakeOffer {
+            collection: String::from("nft_address"),
+            token_id: String::from("GF.2"),
+            price: Asset { info: stable, amount: Uint128::from(1_000_000u128) },
+            expires,
+            referrer: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("thief", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")));
+        let msg = ExecuteMsg::ReceiveToken(Cw20ReceiveMsg {
+            sender: String::from("dave"),
+            amount: Uint128::from(300_000u128),
+            msg: to_binary(&Cw20HookMsg::MakeOffer {
+                collection: String::from("nft_address"),
+                token_id: String::from("GF.1"),
+                expires,
+                referrer: None,
+            }).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("stable", &[]), msg).unwrap();
+
+        // the offer has to be on the sent token
+        let err = execute(deps.as_mut(), mock_env(), mock_info("nft_address", &[]),
+            mock_receive_nft("owner", "GF.1", &Cw721HookMsg::AcceptOffer{ offer_id: 3 })).unwrap_err();
+        assert_eq!(err, ContractError::OfferMismatch{});
+
+        // the owner sells to bob with royalties, the other offers on the token are refunded a batch at a time
+        let msg = ExecuteMsg::SetMaxBatchSize{ size: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("nft_address", &[]),
+            mock_receive_nft("owner", "GF.1", &Cw721HookMsg::AcceptOffer{ offer_id: 2 })).unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("bob"), token_id: String::from("GF.1") }).unwrap(),
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("artist"), amount: coins(12, "uusd") }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("owner"), amount: coins(108, "uusd") }));
+        assert_eq!(res.messages[3].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("alice"), amount: coins(100, "uusd") }));
+        assert!(res.attributes.contains(&attr("offers_pending_refund", "true")));
+
+        // offers made after the sale stay open for the new owner
+        execute(deps.as_mut(), mock_env(), mock_info("eve", &coins(80, "uusd")), offer("GF.1", 80, expires)).unwrap();
+
+        let refund = ExecuteMsg::RefundOffers { collection: String::from("nft_address"), token_id: String::from("GF.1") };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), refund.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("stable"),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: String::from("dave"), amount: Uint128::from(300_000u128) }).unwrap(),
+        }));
+        assert!(!res.attributes.contains(&attr("offers_pending_refund", "true")));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), refund).unwrap_err();
+        assert_eq!(err, ContractError::NoOffersToRefund{});
+
+        let offers = offers_of(deps.as_ref(), Some("GF.1"), None).offers;
+        assert_eq!(offers.iter().map(|offer| offer.id).collect::<Vec<u64>>(), vec![5]);
+        assert!(offers_of(deps.as_ref(), None, Some("alice")).offers.is_empty());
+        assert!(offers_of(deps.as_ref(), None, Some("dave")).offers.is_empty());
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eve", &[]), ExecuteMsg::CancelOffer{ offer_id: 5 }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("eve"), amount: coins(80, "uusd") }));
+        assert_eq!(offers_of(deps.as_ref(), Some("GF.1"), None).offers.len(), 0);
+
+        // expired offers can be cancelled by anybody
+        let mut env = mock_env();
+        env.block.height += 101;
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CancelOffer{ offer_id: 3 }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("carol"), amount: coins(50, "uusd") }));
+    }
+
+    #[test]
+    fn collection_offers() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        let expires = Expiration::AtHeight(mock_env().block.height + 100);
+        let offer = |quantity: u64, traits: OfferTraits| ExecuteMsg::MakeCollectionOffer {
+            collection: String::from("nft_address"),
+            price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(50u128),
+            },
+            quantity,
+            traits: Some(traits),
+            expires,
+            referrer: None,
+        };
+        let single_piece = OfferTraits { collection_id: None, num_real_repr: Some(Uint128::from(1u128)) };
+
+        // the whole quantity is escrowed
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")), offer(3, single_piece.clone()));
+        assert!(res.is_err());
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), offer(0, single_piece.clone())).unwrap_err();
+        assert_eq!(err, ContractError::InvalidOfferQuantity{});
+
+        // cw20 escrows only come in through the Receive hook
+        let msg = ExecuteMsg::AddPaymentAsset {
+            info: AssetInfo::Token{ contract_addr: String::from("stable") },
+            decimals: 6,
+            symbol: String::from("STBL"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::MakeCollectionOffer {
+            collection: String::from("nft_address"),
+            price: Asset { info: AssetInfo::Token{ contract_addr: String::from("stable") }, amount: Uint128::from(500_000u128) },
+            quantity: 2,
+            traits: None,
+            expires,
+            referrer: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("thief", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Std(StdError::generic_err("Cw20 tokens must be sent through the Receive hook")));
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(150, "uusd")), offer(3, single_piece)).unwrap();
+        let in_collection = OfferTraits { collection_id: Some(Uint128::from(7u128)), num_real_repr: None };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(50, "uusd")), offer(1, in_collection)).unwrap();
+
+        let fill = |offer_id: u64, token_id: &str| mock_receive_nft("owner", token_id, &Cw721HookMsg::FillCollectionOffer{ offer_id });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other_nft", &[]), fill(1, "GF.1")).unwrap_err();
+        assert_eq!(err, ContractError::OfferMismatch{});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("nft_address", &[]), fill(2, "GF.1")).unwrap_err();
+        assert_eq!(err, ContractError::TraitMismatch{});
+
+        // each fill pays one share and reduces the quantity
+        let res = execute(deps.as_mut(), mock_env(), mock_info("nft_address", &[]), fill(1, "GF.1")).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_binary(&TransferNft { recipient: String::from("alice"), token_id: String::from("GF.1") }).unwrap(),
+        }));
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("owner"), amount: coins(50, "uusd") }));
+        execute(deps.as_mut(), mock_env(), mock_info("nft_address", &[]), fill(1, "GF.2")).unwrap();
+
+        let msg = QueryMsg::CollectionOffers { collection: None, bidder: Some(String::from("alice")), start_after: None, limit: None };
+        let res: CollectionOffersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.offers.len(), 1);
+        assert_eq!(res.offers[0].quantity, 1);
+        let msg = QueryMsg::CollectionOffers { collection: Some(String::from("nft_address")), bidder: None, start_after: Some(1), limit: None };
+        let res: CollectionOffersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.offers.iter().map(|offer| offer.id).collect::<Vec<u64>>(), vec![2]);
+
+        // the unfilled remainder is refunded at expiry
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecuteMsg::CancelCollectionOffer{ offer_id: 1 }).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized{});
+
+        let mut env = mock_env();
+        env.block.height += 101;
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CancelCollectionOffer{ offer_id: 1 }).unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Bank(BankMsg::Send { to_address: String::from("alice"), amount: coins(50, "uusd") }));
+
+        // cancelled offers leave the indexes
+        let msg = QueryMsg::CollectionOffers { collection: None, bidder: Some(String::from("alice")), start_after: None, limit: None };
+        let res: CollectionOffersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.offers.is_empty());
+    }
+
+    #[test]
+    fn seller_payouts() {
+        let mut deps = mock_dependencies_with_royalties(vec![]);
+        mock_init_with_minter(deps.as_mut());
+
+        let payout = |address: &str, percent: u64| PayoutShare { address: String::from(address), share: Decimal::percent(percent) };
+        let listing = |payouts: Vec<PayoutShare>| ExecuteMsg::PlaceListing {
+            id: String::from("GF.1"),
+            minimum_bid: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(100u128),
+            },
+            escrow: None,
+            nft_contract: None,
+            accepted_assets: None,
+            retraction: None,
+            payouts: Some(payouts),
+            access: None,
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]),
+            listing(vec![payout("gallery", 60), payout("co_owner", 30)])).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPayoutShares{});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]),
+            listing(vec![payout("gallery", 50), payout("gallery", 50)])).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPayoutShares{});
+
+        // the seller receives nothing, the proceeds are split between the recipients
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]),
+            listing(vec![payout("gallery", 60), payout("co_owner", 40)])).unwrap();
+        let msg = ExecuteMsg::BidListing {
+            listing_id: String::from("1"),
+            bid_price: Asset {
+                info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+                amount: Uint128::from(150u128),
+            },
+            expires: None,
+            referrer: None,
+            proof: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(150, "uusd")), msg).unwrap();
+
+        let msg = ExecuteMsg::WithdrawListing{ listing_id: String::from("1") };
+        let res = execute(deps.as_mut(), mock_env_after_auction(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(res.messages[1].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("gallery"),
+            amount: coins(90, "uusd"),
+        }));
+        assert_eq!(res.messages[2].msg, CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("co_owner"),
+            amount: coins(60, "uusd"),
+        }));
+    }
+
+    #[test]
+    fn migrate_legacy_state() {
+        let mut deps = mock_dependencies();
+        let uusd = |amount: u128| Asset {
+            info: AssetInfo::NativeToken{ denom: String::from("uusd") },
+            amount: Uint128::from(amount),
+        };
+
+        // config and a single token listing with a bid, as stored by the first release
+        let config = br#"{"listing_count":1,"owner":"creator","max_aution_duration_blocks":50000}"#;
+        deps.storage.set(&to_length_prefixed(CONFIG_KEY), config);
+        let listing = format!(
+            r#"{{"token_id":"GF.1","contract_addr":"nft_address","seller":"seller","max_bid":{},"max_bidder":"buyer","block_limit":{}}}"#,
+            String::from_utf8(to_vec(&uusd(200)).unwrap()).unwrap(),
+            mock_env().block.height + 10,
+        );
+        deps.storage.set(&[to_length_prefixed(LIST_RESOLVER_KEY), b"1".to_vec()].concat(), listing.as_bytes());
+
+        // the config loads with defaults for the fields added since
+        let config = read_config(deps.as_ref().storage).unwrap();
+        assert_eq!(config.max_batch_size, DEFAULT_MAX_BATCH_SIZE);
+        assert_eq!(config.dispute_window, DEFAULT_DISPUTE_WINDOW);
+        assert_eq!(config.treasury, String::new());
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert!(res.attributes.contains(&attr("migrated_listings", "1")));
+        assert_eq!(read_config(deps.as_ref().storage).unwrap().treasury, String::from("creator"));
+
+        // the listing keeps answering the single token fields and can be bid on
+        let listing: ResolveListingResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::ResolveListing{ id: String::from("1") }).unwrap()).unwrap();
+        assert_eq!((listing.token_id.as_str(), listing.contract_addr.as_str()), ("GF.1", "nft_address"));
+        assert_eq!(listing.items, vec![ListingItem::new(Addr::unchecked("nft_address"), String::from("GF.1"))]);
+
+        // the payment assets registered since are added by the owner
+        let msg = ExecuteMsg::AddPaymentAsset { info: uusd(0).info, decimals: 6, symbol: String::from("UST") };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::BidListing { listing_id: String::from("1"), bid_price: uusd(250), expires: None, referrer: None, proof: None };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(250, "uusd")), msg).unwrap();
+        let listing: ResolveListingResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::ResolveListing{ id: String::from("1") }).unwrap()).unwrap();
+        assert_eq!((listing.max_bidder.as_str(), listing.max_bid.amount), ("bidder", Uint128::from(250u128)));
+
+        let msg = QueryMsg::EndedUnsettled { start_after: None, limit: None };
+        let res: EndedUnsettledResponse = from_binary(&query(deps.as_ref(), mock_env_after_auction(), msg).unwrap()).unwrap();
+        assert_eq!(res.listings, vec![EndedListing { listing_id: String::from("1"), end_block: mock_env().block.height + 10 }]);
+
+        // migrating again changes nothing
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert!(res.attributes.contains(&attr("migrated_listings", "0")));
+    }
+
     // fn assert_config_state(deps: Deps, expected: Config) {
     //     let res = query(deps, mock_env(), QueryMsg::Config {}).unwrap();
     //     let value: Config = from_binary(&res).unwrap();